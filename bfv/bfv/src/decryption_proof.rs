@@ -0,0 +1,429 @@
+//! Non-interactive proofs of correct decryption.
+//!
+//! Owner of secret key `s` of public key `(pk0, pk1) = (-a*s + e, a)` proves that plaintext `m`
+//! is the decryption of ciphertext `(c0, c1)`, that is that there exist short `s`, `e` and `v`
+//! such that
+//!
+//! ```text
+//!     pk1*s - e = -pk0
+//!     c1*s - v = -(c0 - delta*m)
+//! ```
+//!
+//! where `v` is the decryption noise. Since the public key binds `s` and `|v| < delta/2`, `m` is
+//! the only plaintext that `(c0, c1)` decrypts to.
+//!
+//! Proof is a sigma protocol with rejection sampling (Fiat-Shamir with aborts) made
+//! non-interactive by deriving the challenge from the hash of the statement and the commitment.
+//! Verifier only needs the public key, the ciphertext and the plaintext. Proof reveals bit sizes
+//! of `e` and `v`. Noise of ciphertexts returned by `Evaluator::sanitize` is dominated by
+//! flooding noise, thus bit size of `v` reveals nothing about the computation.
+
+use crate::key_switching_key::drop_moduli;
+use crate::{
+    BfvParameters, Ciphertext, Encoding, Plaintext, Poly, PolyCache, PolyContext, PolyType,
+    PublicKey, Representation, SecretKey,
+};
+use itertools::Itertools;
+use ndarray::Array2;
+use num_bigint::{BigUint, RandBigInt};
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha3::{Digest, Sha3_256};
+use zeroize::Zeroizing;
+
+/// No. of non-zero (ie `1` or `-1`) coefficients of a challenge polynomial
+const CHALLENGE_WEIGHT: usize = 60;
+
+/// Proof that a `Plaintext` is the decryption of a `Ciphertext` under secret key of a
+/// `PublicKey`. See module docs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecryptionProof {
+    pub(crate) challenge: [u8; 32],
+    pub(crate) z_s: Poly,
+    pub(crate) z_e: Poly,
+    pub(crate) z_v: Poly,
+    /// bit size of `||e||_inf`
+    pub(crate) key_noise_bits: u64,
+    /// bit size of `||v||_inf`
+    pub(crate) noise_bits: u64,
+    /// level of the ciphertext
+    pub(crate) level: usize,
+}
+
+impl DecryptionProof {
+    /// Proves that `pt` (for ex, output of `Evaluator::decrypt`) is the decryption of `ct`
+    /// under `sk`, the secret key of `pk`.
+    ///
+    /// Panics if `ct` is not a decryption of `pt`, if noise of `ct` is too large for the proof to
+    /// be sound or if any of `pk`, `ct` and `pt` does not match `params`.
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        pk: &PublicKey,
+        ct: &Ciphertext,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> DecryptionProof {
+        let statement = Statement::new(params, pk, ct, pt).expect("Malformed statement");
+        let ctx = &statement.ctx;
+
+        let s = sk.to_poly(ctx);
+
+        // e = pk0 + pk1*s, v = c0 - delta*m + c1*s
+        let [e, v] = [&statement.pk, &statement.ct].map(|[p0, p1]| {
+            let mut w = Zeroizing::new(ctx.mul(p1, &s));
+            ctx.add_assign(&mut w, p0);
+            ctx.change_representation(&mut w, Representation::Coefficient);
+            w
+        });
+        let [key_noise_bits, noise_bits] = [&e, &v].map(|w| inf_norm_bits(ctx, w));
+        assert!(
+            statement.is_sound(noise_bits),
+            "Noise of ciphertext is too large to prove decryption"
+        );
+
+        let mut s_coefficient = Zeroizing::new(Poly::clone(&s));
+        ctx.change_representation(&mut s_coefficient, Representation::Coefficient);
+        let witness = [s_coefficient, e, v];
+        let bounds = Bounds::new(ctx.degree, key_noise_bits, noise_bits);
+
+        // Rejection sampling: responses are accepted only if they are independent of the witness
+        loop {
+            let y = bounds
+                .masking
+                .each_ref()
+                .map(|b| Zeroizing::new(sample_uniform(ctx, b, rng)));
+            let [y_s, y_e, y_v] = y.each_ref().map(|y| {
+                let mut y = Poly::clone(y);
+                ctx.change_representation(&mut y, Representation::Evaluation);
+                Zeroizing::new(y)
+            });
+
+            // w1 = pk1*y_s - y_e, w2 = c1*y_s - y_v
+            let commitments = [(&statement.pk[1], &y_e), (&statement.ct[1], &y_v)]
+                .map(|(p1, y)| ctx.sub(&ctx.mul(p1, &y_s), y));
+            let challenge = statement.challenge(&commitments, key_noise_bits, noise_bits);
+            let c = challenge_poly(ctx, &challenge);
+
+            // z = y + c*w
+            let [z_s, z_e, z_v] = [0, 1, 2].map(|i| {
+                let mut z = ctx.mul(&c, &to_evaluation(ctx, &witness[i]));
+                ctx.change_representation(&mut z, Representation::Coefficient);
+                ctx.add_assign(&mut z, &y[i]);
+                z
+            });
+
+            if bounds.accepts(ctx, [&z_s, &z_e, &z_v]) {
+                return DecryptionProof {
+                    challenge,
+                    z_s,
+                    z_e,
+                    z_v,
+                    key_noise_bits,
+                    noise_bits,
+                    level: statement.level,
+                };
+            }
+        }
+    }
+
+    /// Returns true if proof is valid, that is if `pt` is the decryption of `ct` under secret key
+    /// of `pk`. Returns false, instead of panicking, on malformed proofs and on `pk`, `ct` or `pt`
+    /// that don't match `params`.
+    pub fn verify(
+        &self,
+        params: &BfvParameters,
+        pk: &PublicKey,
+        ct: &Ciphertext,
+        pt: &Plaintext,
+    ) -> bool {
+        let statement = match Statement::new(params, pk, ct, pt) {
+            Some(statement) => statement,
+            None => return false,
+        };
+        let ctx = &statement.ctx;
+
+        if self.level != statement.level || !statement.is_sound(self.noise_bits) {
+            return false;
+        }
+        let bounds = Bounds::new(ctx.degree, self.key_noise_bits, self.noise_bits);
+        if !bounds.accepts(ctx, [&self.z_s, &self.z_e, &self.z_v]) {
+            return false;
+        }
+
+        // w1 = pk1*z_s - z_e + c*pk0, w2 = c1*z_s - z_v + c*(c0 - delta*m)
+        let c = challenge_poly(ctx, &self.challenge);
+        let z_s = to_evaluation(ctx, &self.z_s);
+        let commitments =
+            [(&statement.pk, &self.z_e), (&statement.ct, &self.z_v)].map(|([p0, p1], z)| {
+                let mut w = ctx.mul(p1, &z_s);
+                ctx.sub_assign(&mut w, &to_evaluation(ctx, z));
+                ctx.add_assign(&mut w, &ctx.mul(&c, p0));
+                w
+            });
+
+        statement.challenge(&commitments, self.key_noise_bits, self.noise_bits) == self.challenge
+    }
+
+    /// Returns a proof that `verify` always rejects. Used in place of proofs that fail to decode.
+    pub(crate) fn malformed() -> DecryptionProof {
+        let empty = Poly {
+            coefficients: Array2::zeros((0, 0)),
+            representation: Representation::Coefficient,
+        };
+        DecryptionProof {
+            challenge: [0; 32],
+            z_s: empty.clone(),
+            z_e: empty.clone(),
+            z_v: empty,
+            key_noise_bits: 0,
+            noise_bits: 0,
+            level: 0,
+        }
+    }
+}
+
+/// Public key `[pk0, pk1]` and `[c0 - delta*m, c1]` in Q basis at level of the ciphertext and
+/// `Evaluation` representation
+struct Statement<'a> {
+    ctx: PolyContext<'a>,
+    pk: [Poly; 2],
+    ct: [Poly; 2],
+    level: usize,
+    /// `floor(Q/t)`
+    delta: BigUint,
+}
+
+impl<'a> Statement<'a> {
+    /// Returns None if `ct` is not a 2 polynomial ciphertext in Q basis, or if any of `pk`, `ct`
+    /// and `pt` does not match `params`
+    fn new(
+        params: &'a BfvParameters,
+        pk: &PublicKey,
+        ct: &Ciphertext,
+        pt: &Plaintext,
+    ) -> Option<Statement<'a>> {
+        if ct.c.len() != 2
+            || ct.poly_type != PolyType::Q
+            || ct.level > params.max_level
+            || pt.m.len() != params.degree
+            || pt.m.iter().any(|v| *v >= params.plaintext_modulus)
+        {
+            return None;
+        }
+
+        let level = ct.level;
+        let ctx = params.poly_ctx(&PolyType::Q, level);
+        // public key is in Q basis at level 0, thus may have more moduli than `ctx`
+        if !ct.c.iter().all(|p| has_shape(&ctx, p, false))
+            || ![&pk.c0, &pk.c1].iter().all(|p| has_shape(&ctx, p, true))
+        {
+            return None;
+        }
+
+        let pk = [&pk.c0, &pk.c1].map(|p| drop_moduli(p, &ctx).into_owned());
+        let mut ct = [0, 1].map(|i| to_evaluation(&ctx, &ct.c[i]));
+        let m = Plaintext::scale_m(
+            &pt.m,
+            params,
+            &Encoding::simd(level, PolyCache::None),
+            Representation::Evaluation,
+        );
+        ctx.sub_assign(&mut ct[0], &m);
+
+        let delta = ctx.big_q() / params.plaintext_modulus;
+        Some(Statement {
+            ctx,
+            pk,
+            ct,
+            level,
+            delta,
+        })
+    }
+
+    /// Returns true if any decryption noise of `noise_bits` bits that an accepting proof implies
+    /// (ie `2 * masking bound` of `v`) is less than `delta/2`
+    fn is_sound(&self, noise_bits: u64) -> bool {
+        let bound = Bounds::new(self.ctx.degree, 0, noise_bits).masking[2].clone();
+        (bound << 2) < self.delta
+    }
+
+    /// Returns Fiat-Shamir challenge: hash of the statement and of the commitments
+    fn challenge(&self, commitments: &[Poly; 2], key_noise_bits: u64, noise_bits: u64) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"bfv-decryption-proof");
+        hasher.update((self.ctx.degree as u64).to_le_bytes());
+        hasher.update((self.level as u64).to_le_bytes());
+        self.ctx
+            .iter_moduli_ops()
+            .for_each(|qi| hasher.update(qi.modulus().to_le_bytes()));
+        hasher.update(key_noise_bits.to_le_bytes());
+        hasher.update(noise_bits.to_le_bytes());
+        self.pk
+            .iter()
+            .chain(self.ct.iter())
+            .chain(commitments.iter())
+            .for_each(|p| {
+                p.coefficients
+                    .iter()
+                    .for_each(|v| hasher.update(v.to_le_bytes()))
+            });
+        hasher.finalize().into()
+    }
+}
+
+/// Bounds on `s`, `e` and `v` and their masks
+struct Bounds {
+    /// `||c*w||_inf` for every witness `w`
+    witness: [BigUint; 3],
+    /// masks are sampled uniformly from `[-bound, bound]`
+    masking: [BigUint; 3],
+}
+
+impl Bounds {
+    fn new(degree: usize, key_noise_bits: u64, noise_bits: u64) -> Bounds {
+        let weight = CHALLENGE_WEIGHT.min(degree);
+        let witness = [
+            BigUint::from(weight),
+            BigUint::from(weight) << key_noise_bits,
+            BigUint::from(weight) << noise_bits,
+        ];
+        // each response is accepted with probability ~ e^{-1/2}, thus all three are accepted
+        // with probability ~ e^{-3/2}
+        let masking = witness.clone().map(|b| b * (2 * degree));
+        Bounds { witness, masking }
+    }
+
+    /// Returns true if `||z_i||_inf <= masking_i - witness_i` for every response `z_i`
+    fn accepts(&self, ctx: &PolyContext<'_>, z: [&Poly; 3]) -> bool {
+        z.iter().enumerate().all(|(i, z)| {
+            z.representation == Representation::Coefficient
+                && has_shape(ctx, z, false)
+                && inf_norm(ctx, z) <= &self.masking[i] - &self.witness[i]
+        })
+    }
+}
+
+/// Returns true if `p` has `ctx.degree` coefficients modulo every modulus of `ctx` (or modulo
+/// more moduli if `extra_moduli` is set) and known representation
+pub(crate) fn has_shape(ctx: &PolyContext<'_>, p: &Poly, extra_moduli: bool) -> bool {
+    let [rows, columns] = [0, 1].map(|i| p.coefficients.shape()[i]);
+    let rows_match = if extra_moduli {
+        rows >= ctx.moduli_count
+    } else {
+        rows == ctx.moduli_count
+    };
+    rows_match && columns == ctx.degree && p.representation != Representation::Unknown
+}
+
+fn to_evaluation(ctx: &PolyContext<'_>, p: &Poly) -> Poly {
+    let mut p = p.clone();
+    ctx.change_representation(&mut p, Representation::Evaluation);
+    p
+}
+
+/// Returns `||p||_inf` of `p` in `Coefficient` representation, with coefficients in
+/// `[-Q/2, Q/2)`
+fn inf_norm(ctx: &PolyContext<'_>, p: &Poly) -> BigUint {
+    let q = ctx.big_q();
+    ctx.try_convert_to_biguint(p)
+        .into_iter()
+        .map(|v| std::cmp::min(&q - &v, v))
+        .max()
+        .unwrap_or_default()
+}
+
+fn inf_norm_bits(ctx: &PolyContext<'_>, p: &Poly) -> u64 {
+    inf_norm(ctx, p).bits()
+}
+
+/// Samples polynomial with coefficients uniform in `[-bound, bound]` in `Coefficient`
+/// representation
+fn sample_uniform<R: CryptoRng + RngCore>(
+    ctx: &PolyContext<'_>,
+    bound: &BigUint,
+    rng: &mut R,
+) -> Poly {
+    let range = (bound << 1) + 1u64;
+    let values = (0..ctx.degree)
+        .map(|_| rng.gen_biguint_below(&range))
+        .collect_vec();
+    let mut p = ctx.try_convert_from_biguint(&values, Representation::Coefficient);
+    let offset = ctx.try_convert_from_biguint(
+        vec![bound.clone(); ctx.degree].as_slice(),
+        Representation::Coefficient,
+    );
+    ctx.sub_assign(&mut p, &offset);
+    p
+}
+
+/// Expands `challenge` into polynomial with `CHALLENGE_WEIGHT` coefficients in `{-1, 1}` and
+/// rest 0, in `Evaluation` representation
+fn challenge_poly(ctx: &PolyContext<'_>, challenge: &[u8; 32]) -> Poly {
+    let mut rng = ChaCha20Rng::from_seed(*challenge);
+    let mut values = vec![0i64; ctx.degree];
+    rand::seq::index::sample(&mut rng, ctx.degree, CHALLENGE_WEIGHT.min(ctx.degree))
+        .into_iter()
+        .for_each(|i| values[i] = if rng.gen_bool(0.5) { 1 } else { -1 });
+    let mut c = ctx.try_convert_from_i64_small(&values, Representation::Coefficient);
+    ctx.change_representation(&mut c, Representation::Evaluation);
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Evaluator;
+    use rand::thread_rng;
+
+    #[test]
+    fn decryption_proof_works() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(4, 1 << 4);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let pk = PublicKey::new(&params, &sk, &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let m = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(evaluator.params().degree, &mut rng);
+        let pt = evaluator.plaintext_encode(&m, Encoding::simd(1, PolyCache::None));
+        let ct = evaluator.encrypt_with_public_key(&pk, &pt, &mut rng);
+        let ct = evaluator.sanitize(&ct, &pk, &mut rng);
+
+        let pt = evaluator.decrypt(&sk, &ct);
+        let proof = DecryptionProof::new(evaluator.params(), &sk, &pk, &ct, &pt, &mut rng);
+        assert!(proof.verify(evaluator.params(), &pk, &ct, &pt));
+        assert_eq!(evaluator.plaintext_decode(&pt, Encoding::default()), m);
+
+        // wrong plaintext
+        let mut m_other = m.clone();
+        m_other[0] = (m_other[0] + 1) % evaluator.params().plaintext_modulus;
+        let pt_other = evaluator.plaintext_encode(&m_other, Encoding::simd(1, PolyCache::None));
+        assert!(!proof.verify(evaluator.params(), &pk, &ct, &pt_other));
+
+        // wrong public key
+        let sk_other = SecretKey::random_with_params(evaluator.params(), &mut rng);
+        let pk_other = PublicKey::new(evaluator.params(), &sk_other, &mut rng);
+        assert!(!proof.verify(evaluator.params(), &pk_other, &ct, &pt));
+
+        // wrong ciphertext
+        let ct_other = evaluator.encrypt_with_public_key(&pk, &pt_other, &mut rng);
+        assert!(!proof.verify(evaluator.params(), &pk, &ct_other, &pt));
+
+        // malformed ciphertext
+        let mut ct_malformed = ct.clone();
+        ct_malformed.c.push(ct.c[1].clone());
+        assert!(!proof.verify(evaluator.params(), &pk, &ct_malformed, &pt));
+
+        // malformed proof
+        let mut proof_malformed = proof.clone();
+        proof_malformed.z_s.coefficients = proof
+            .z_s
+            .coefficients
+            .slice(ndarray::s![1.., ..])
+            .to_owned();
+        assert!(!proof_malformed.verify(evaluator.params(), &pk, &ct, &pt));
+    }
+}
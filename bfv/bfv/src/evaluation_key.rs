@@ -1,8 +1,51 @@
-use crate::{rot_to_galois_element, BfvParameters, GaloisKey, RelinearizationKey, SecretKey};
+use crate::{naf, rot_to_galois_element, BfvParameters, GaloisKey, RelinearizationKey, SecretKey};
 use itertools::{izip, Itertools};
 use rand::{CryptoRng, RngCore};
 use std::collections::HashMap;
 
+/// Set of rotation keys to generate
+#[derive(Clone, Debug, PartialEq)]
+pub enum RotationKeyPolicy {
+    /// Keys for rotations by `±2^i` for every `2^i < degree/4`, by `degree/4` and row swap. Any
+    /// rotation is composed of at most `log2(degree)/2` of these (see
+    /// `EvaluationKey::rotation_steps`).
+    PowersOfTwo,
+    /// Keys for given rotation indices only
+    Explicit(Vec<isize>),
+}
+
+impl RotationKeyPolicy {
+    /// Returns rotation indices of keys to generate for polynomial `degree`
+    pub fn indices(&self, degree: usize) -> Vec<isize> {
+        match self {
+            RotationKeyPolicy::PowersOfTwo => {
+                let half = (degree / 4) as isize;
+                let mut indices = vec![];
+                let mut power = 1;
+                while power < half {
+                    indices.push(power);
+                    indices.push(-power);
+                    power <<= 1;
+                }
+                // -degree/4 rotates by the same amount as degree/4
+                indices.push(half);
+                indices.push((2 * degree - 1) as isize);
+                indices
+            }
+            RotationKeyPolicy::Explicit(indices) => indices.clone(),
+        }
+    }
+}
+
+/// Returns Galois element of rotation by `index`, where `index = 2 * degree - 1` denotes row swap
+pub(crate) fn rtg_galois_element(index: isize, degree: usize) -> usize {
+    if index == (2 * degree - 1) as isize {
+        2 * degree - 1
+    } else {
+        rot_to_galois_element(index, degree)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct EvaluationKey {
     pub(crate) rlks: HashMap<usize, RelinearizationKey>,
@@ -27,13 +70,7 @@ impl EvaluationKey {
 
         let mut rtgs = HashMap::new();
         izip!(rtg_indices.iter(), rtg_levels.iter()).for_each(|(index, level)| {
-            let el = {
-                if *index == (2 * params.degree - 1) as isize {
-                    2 * params.degree - 1
-                } else {
-                    rot_to_galois_element(*index, params.degree)
-                }
-            };
+            let el = rtg_galois_element(*index, params.degree);
             rtgs.insert(
                 (*index, *level),
                 GaloisKey::new(el, params, *level, sk, rng),
@@ -43,8 +80,95 @@ impl EvaluationKey {
         EvaluationKey { rlks, rtgs }
     }
 
+    /// Returns evaluation key consisting of given keys, where every Galois key is paired with its
+    /// rotation index. For ex, keys aggregated from shares of several parties (see `threshold`).
+    pub fn from_keys(
+        rlks: Vec<RelinearizationKey>,
+        rtgs: Vec<(isize, GaloisKey)>,
+    ) -> EvaluationKey {
+        EvaluationKey {
+            rlks: rlks.into_iter().map(|k| (k.level, k)).collect(),
+            rtgs: rtgs
+                .into_iter()
+                .map(|(index, k)| ((index, k.level), k))
+                .collect(),
+        }
+    }
+
+    /// Generates relinearization keys at `rlk_levels` and rotation keys of `policy` at
+    /// `rtg_level`. Since keys are usable at any level greater than or equal to level they were
+    /// generated at (see `get_rlk`), `rtg_level` is usually 0.
+    pub fn with_policy<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rlk_levels: &[usize],
+        rtg_level: usize,
+        policy: &RotationKeyPolicy,
+        rng: &mut R,
+    ) -> EvaluationKey {
+        let rtg_indices = policy.indices(params.degree);
+        let rtg_levels = vec![rtg_level; rtg_indices.len()];
+        EvaluationKey::new(params, sk, rlk_levels, &rtg_levels, &rtg_indices, rng)
+    }
+
+    /// Returns relinearization key usable at `level`, ie key at `level` if present, otherwise key
+    /// at closest lower level. Keys are level agnostic: key generated at level `l` can be used for
+    /// ciphertexts at any level `>= l` by dropping moduli, thus generating keys only at level 0
+    /// suffices.
+    pub fn get_rlk(&self, level: usize) -> Option<&RelinearizationKey> {
+        (0..=level).rev().find_map(|l| self.rlks.get(&l))
+    }
+
+    /// Returns Galois key for `rot_by` usable at `level` (see `get_rlk`).
+    pub fn get_rtg(&self, rot_by: isize, level: usize) -> Option<&GaloisKey> {
+        (0..=level).rev().find_map(|l| self.rtgs.get(&(rot_by, l)))
+    }
+
     pub fn get_rtg_ref(&self, rot_by: isize, level: usize) -> &GaloisKey {
-        self.rtgs.get(&(rot_by, level)).expect("Rtg missing!")
+        self.get_rtg(rot_by, level).expect("Rtg missing!")
+    }
+
+    /// Returns rotation indices of available Galois keys at `level` whose composition rotates by
+    /// `rot_by`, or `None` if `rot_by` cannot be composed of available keys.
+    ///
+    /// Row swap (ie `rot_by = 2 * degree - 1`) requires its own key. Otherwise rotation amounts
+    /// are taken modulo row size `degree/2`. `rot_by` is decomposed into `±2^i` along its NAF
+    /// (see `naf`), trying both `rot_by` and `rot_by - degree/2`, and the shorter decomposition
+    /// is returned. A key is used directly if available for `rot_by`.
+    pub fn rotation_steps(&self, rot_by: isize, degree: usize, level: usize) -> Option<Vec<isize>> {
+        if self.get_rtg(rot_by, level).is_some() {
+            return Some(vec![rot_by]);
+        }
+
+        let row_swap = (2 * degree - 1) as isize;
+        if rot_by == row_swap {
+            return None;
+        }
+
+        let row_size = (degree / 2) as isize;
+        let r = rot_by.rem_euclid(row_size);
+        if r == 0 {
+            return Some(vec![]);
+        }
+
+        // key for step `i`, `i - row_size` or `i + row_size` rotates by `i`
+        let key_index = |i: isize| -> Option<isize> {
+            [i, i - row_size, i + row_size]
+                .into_iter()
+                .find(|j| *j != row_swap && self.get_rtg(*j, level).is_some())
+        };
+
+        [r, r - row_size]
+            .into_iter()
+            .filter_map(|amount| {
+                naf(amount)
+                    .into_iter()
+                    // rotation by a multiple of row size is identity
+                    .filter(|digit| digit.rem_euclid(row_size) != 0)
+                    .map(key_index)
+                    .collect::<Option<Vec<isize>>>()
+            })
+            .min_by_key(|steps| steps.len())
     }
 }
 
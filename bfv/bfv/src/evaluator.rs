@@ -1,10 +1,18 @@
 use crate::relinearization_key::RelinearizationKey;
 use crate::{BfvParameters, Ciphertext, EvaluationKey, PolyType};
-use crate::{Encoding, GaloisKey, Plaintext, SecretKey};
-use crate::{Poly, Representation};
+use crate::{
+    Encoding, GaloisKey, KeySwitchingKey, Plaintext, PublicKey, ReEncryptionKey, SecretKey,
+};
+use crate::{Poly, PolyCache, Representation};
 use itertools::{izip, Itertools};
-use num_bigint::{BigUint, RandBigInt};
-use rand::{thread_rng, CryptoRng, Rng, RngCore};
+use num_bigint::BigUint;
+use rand::{CryptoRng, Rng, RngCore};
+
+/// Statistical security (in bits) of flooding noise added by `Evaluator::sanitize`
+pub(crate) const FLOODING_STATISTICAL_SECURITY: usize = 40;
+/// Bits of noise budget, in addition to `log2(N)`, left after `Evaluator::sanitize`, so that
+/// the result remains within the noise `DecryptionProof` can prove
+const SANITIZED_NOISE_MARGIN: u64 = 12;
 
 pub struct Evaluator {
     pub(crate) params: BfvParameters,
@@ -173,17 +181,83 @@ impl Evaluator {
     }
 
     pub fn relinearize(&self, c0: &Ciphertext, ek: &EvaluationKey) -> Ciphertext {
-        ek.rlks
-            .get(&c0.level)
+        ek.get_rlk(c0.level)
             .expect("Rlk missing!")
             .relinearize(&c0, &self.params)
     }
 
+    /// Rotates `c0` by `rotate_by`.
+    ///
+    /// Uses key for `rotate_by` if available, otherwise composes rotation of available keys (see
+    /// `EvaluationKey::rotation_steps`). Panics if `rotate_by` cannot be composed.
     pub fn rotate(&self, c0: &Ciphertext, rotate_by: isize, ek: &EvaluationKey) -> Ciphertext {
-        ek.rtgs
-            .get(&(rotate_by, c0.level))
-            .expect(&format!("Rtg missing! :{rotate_by} {}", c0.level))
-            .rotate(&c0, &self.params)
+        let level = c0.level;
+        let steps = ek
+            .rotation_steps(rotate_by, self.params.degree, level)
+            .expect(&format!("Rtg missing! :{rotate_by} {level}"));
+
+        match steps.split_first() {
+            Some((first, rest)) => {
+                let ct = ek.get_rtg_ref(*first, level).rotate(c0, &self.params);
+                self.rotate_steps(ct, rest, ek)
+            }
+            None => c0.clone(),
+        }
+    }
+
+    /// Applies rotations by `steps` to `ct` one after another
+    fn rotate_steps(&self, ct: Ciphertext, steps: &[isize], ek: &EvaluationKey) -> Ciphertext {
+        steps.iter().fold(ct, |ct, step| {
+            ek.get_rtg_ref(*step, ct.level).rotate(&ct, &self.params)
+        })
+    }
+
+    /// Returns rotations of `c0` by every amount in `rotate_by`.
+    ///
+    /// Rotations are hoisted: `c1` is decomposed for key switching once and the decomposition is
+    /// shared among all Galois keys. Thus rotating by `k` amounts costs a single decomposition
+    /// instead of `k`. Rotations without a key of their own are composed as in `rotate`, where
+    /// only the first step is hoisted.
+    pub fn rotate_many(
+        &self,
+        c0: &Ciphertext,
+        rotate_by: &[isize],
+        ek: &EvaluationKey,
+    ) -> Vec<Ciphertext> {
+        let level = c0.level;
+        let steps = rotate_by
+            .iter()
+            .map(|r| {
+                ek.rotation_steps(*r, self.params.degree, level)
+                    .expect(&format!("Rtg missing! :{r} {level}"))
+            })
+            .collect_vec();
+        if steps.iter().all(|s| s.is_empty()) {
+            return vec![c0.clone(); rotate_by.len()];
+        }
+
+        let mut c1 = c0.c[1].clone();
+        if c1.representation == Representation::Evaluation {
+            self.params
+                .poly_ctx(&PolyType::Q, level)
+                .change_representation(&mut c1, Representation::Coefficient);
+        }
+        let decomposed = KeySwitchingKey::decompose(&self.params, &c1, level);
+
+        steps
+            .iter()
+            .map(|s| match s.split_first() {
+                Some((first, rest)) => {
+                    let ct = ek.get_rtg_ref(*first, level).rotate_decomposed(
+                        c0,
+                        &decomposed,
+                        &self.params,
+                    );
+                    self.rotate_steps(ct, rest, ek)
+                }
+                None => c0.clone(),
+            })
+            .collect()
     }
 
     pub fn add_assign(&self, c0: &mut Ciphertext, c1: &Ciphertext) {
@@ -382,6 +456,11 @@ impl Evaluator {
         Plaintext::encode(m, &self.params, encoding)
     }
 
+    /// Encodes signed message `m`. See `Plaintext::encode_signed`.
+    pub fn plaintext_encode_signed(&self, m: &[i64], encoding: Encoding) -> Plaintext {
+        Plaintext::encode_signed(m, &self.params, encoding)
+    }
+
     pub fn encrypt<R: RngCore + CryptoRng>(
         &self,
         sk: &SecretKey,
@@ -391,6 +470,20 @@ impl Evaluator {
         sk.encrypt(&self.params, pt, rng)
     }
 
+    pub fn encrypt_with_public_key<R: RngCore + CryptoRng>(
+        &self,
+        pk: &PublicKey,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Ciphertext {
+        pk.encrypt(&self.params, pt, rng)
+    }
+
+    /// Re-encrypts `ct` to the recipient of `ksk` (see `ReEncryptionKey`)
+    pub fn switch_key(&self, ct: &Ciphertext, ksk: &ReEncryptionKey) -> Ciphertext {
+        ksk.switch(ct, &self.params)
+    }
+
     pub fn decrypt(&self, sk: &SecretKey, ct: &Ciphertext) -> Plaintext {
         sk.decrypt(ct, &self.params)
     }
@@ -399,27 +492,86 @@ impl Evaluator {
         pt.decode(encoding, &self.params)
     }
 
+    /// Decodes plaintext to signed message. See `Plaintext::decode_signed`.
+    pub fn plaintext_decode_signed(&self, pt: &Plaintext, encoding: Encoding) -> Vec<i64> {
+        pt.decode_signed(encoding, &self.params)
+    }
+
     pub fn measure_noise(&self, sk: &SecretKey, ct: &Ciphertext) -> u64 {
         sk.measure_noise(ct, &self.params)
     }
 
-    pub unsafe fn add_noise(&self, c0: &mut Ciphertext, bit_size: usize) {
-        let ctx = self.params.poly_ctx(&c0.poly_type, c0.level);
+    /// Returns largest noise (in bits) of ciphertexts at `level` that `sanitize` hides: the
+    /// largest noise whose flooding keeps noise of the result `SANITIZED_NOISE_MARGIN + log2(N)`
+    /// bits below `Q/2t`.
+    ///
+    /// Bound only depends on `level` and parameters, thus can be checked without secret key. It
+    /// is the default for circuits without a noise estimate of their outputs and uses up almost
+    /// all noise budget, see `sanitize_with_noise_bits`.
+    pub fn sanitize_noise_bound(&self, level: usize) -> u64 {
+        self.noise_budget_bits(level)
+            .saturating_sub(SANITIZED_NOISE_MARGIN + self.params.degree.ilog2() as u64)
+            .saturating_sub(FLOODING_STATISTICAL_SECURITY as u64)
+    }
 
-        // sample biguint
-        let mut rng = thread_rng();
-        let biguints = (0..ctx.degree)
-            .into_iter()
-            .map(|_| rng.gen_biguint(bit_size as u64))
-            .collect_vec();
+    /// Returns bit size of `Q/2t` at `level`
+    fn noise_budget_bits(&self, level: usize) -> u64 {
+        let ctx = self.params.poly_ctx(&PolyType::Q, level);
+        (ctx.big_q() / (2 * self.params.plaintext_modulus)).bits()
+    }
+
+    /// Same as `sanitize_with_noise_bits` with noise of `ct` assumed to be within
+    /// `sanitize_noise_bound(level)`.
+    pub fn sanitize<R: CryptoRng + RngCore>(
+        &self,
+        ct: &Ciphertext,
+        pk: &PublicKey,
+        rng: &mut R,
+    ) -> Ciphertext {
+        let noise_bits = self.sanitize_noise_bound(ct.level);
+        self.sanitize_with_noise_bits(ct, pk, noise_bits, rng)
+    }
 
-        let mut noise_poly = ctx.try_convert_from_biguint(&biguints, Representation::Coefficient);
-        ctx.change_representation(&mut noise_poly, c0.c_ref()[0].representation.clone());
+    /// Sanitizes `ct` before it is returned to a client, so that its decryption reveals nothing
+    /// about the computation (ie other inputs) beyond the plaintext.
+    ///
+    /// Re-randomizes `ct` with a fresh encryption of zero under `pk` and floods its noise with
+    /// noise of `noise_bits + 40` bits, which statistically hides noise of `ct` with 40 bits of
+    /// security as long as `noise_bits` (for ex, from noise analysis of the circuit) bounds noise
+    /// of `ct`. Tighter estimate leaves more noise budget for computation on the result.
+    ///
+    /// Panics if flooding noise does not fit below `Q/2t` at the level of `ct`.
+    pub fn sanitize_with_noise_bits<R: CryptoRng + RngCore>(
+        &self,
+        ct: &Ciphertext,
+        pk: &PublicKey,
+        noise_bits: u64,
+        rng: &mut R,
+    ) -> Ciphertext {
+        assert!(ct.c.len() == 2);
+        assert!(ct.poly_type == PolyType::Q);
+        assert!(ct.c[0].representation == Representation::Coefficient);
 
-        c0.c_ref_mut().iter_mut().for_each(|p| {
-            ctx.add_assign(p, &noise_poly);
-        });
-        c0.seed = None;
+        let level = ct.level;
+        let ctx = self.params.poly_ctx(&PolyType::Q, level);
+
+        let flooding_bits = noise_bits as usize + FLOODING_STATISTICAL_SECURITY;
+        assert!(
+            (flooding_bits as u64) < self.noise_budget_bits(level),
+            "Insufficient noise budget for flooding {noise_bits} bits of noise at level {level}"
+        );
+
+        let zero = Plaintext::encode(
+            &vec![0; self.params.degree],
+            &self.params,
+            Encoding::simd(level, PolyCache::None),
+        );
+        let mut res = self.add(ct, &pk.encrypt(&self.params, &zero, rng));
+        ctx.add_assign(
+            &mut res.c[0],
+            &ctx.random_flooding(Representation::Coefficient, flooding_bits, rng),
+        );
+        res
     }
 }
 
@@ -429,7 +581,10 @@ mod tests {
 
     use rand::thread_rng;
 
-    use crate::{relinearization_key::RelinearizationKey, utils::rot_to_galois_element, PolyCache};
+    use crate::{
+        relinearization_key::RelinearizationKey, utils::rot_to_galois_element, PolyCache,
+        RotationKeyPolicy,
+    };
 
     use super::*;
 
@@ -622,6 +777,213 @@ mod tests {
         dbg!(&res_m, &m0);
     }
 
+    #[test]
+    fn rotate_many_works() {
+        let mut rng = thread_rng();
+        let mut params = BfvParameters::new(&[50; 3], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[50, 50, 50]);
+
+        // gen keys
+        let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+        let rotate_by = [1, 2, 3, -1, 2 * params.degree as isize - 1];
+        let ek = EvaluationKey::new(&params, &sk, &[], &[0; 5], &rotate_by, &mut rng);
+
+        let m0 = params
+            .plaintext_modulus_op
+            .random_vec(params.degree, &mut rng);
+
+        let evaluator = Evaluator::new(params);
+        let pt0 = evaluator.plaintext_encode(&m0, Encoding::default());
+        let ct0 = evaluator.encrypt(&sk, &pt0, &mut rng);
+
+        let hoisted = evaluator.rotate_many(&ct0, &rotate_by, &ek);
+        assert_eq!(hoisted.len(), rotate_by.len());
+        izip!(rotate_by.iter(), hoisted.iter()).for_each(|(r, ct)| {
+            let expected = evaluator.plaintext_decode(
+                &evaluator.decrypt(&sk, &evaluator.rotate(&ct0, *r, &ek)),
+                Encoding::default(),
+            );
+            let res_m =
+                evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default());
+            assert_eq!(res_m, expected);
+        });
+    }
+
+    #[test]
+    fn level_agnostic_keys_work() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(8, 1 << 4);
+
+        // keys only at level 0
+        let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[0, 0], &[1, 3], &mut rng);
+
+        let m0 = params
+            .plaintext_modulus_op
+            .random_vec(params.degree, &mut rng);
+        let m1 = params
+            .plaintext_modulus_op
+            .random_vec(params.degree, &mut rng);
+
+        let evaluator = Evaluator::new(params);
+        let pt0 = evaluator.plaintext_encode(&m0, Encoding::default());
+        let pt1 = evaluator.plaintext_encode(&m1, Encoding::default());
+        let ct0 = evaluator.encrypt(&sk, &pt0, &mut rng);
+        let ct1 = evaluator.encrypt(&sk, &pt1, &mut rng);
+
+        let mut m0m1 = m0.clone();
+        evaluator
+            .params
+            .plaintext_modulus_op
+            .mul_mod_fast_vec(&mut m0m1, &m1);
+        let expected_rotations = [1, 3]
+            .iter()
+            .map(|r| {
+                evaluator.plaintext_decode(
+                    &evaluator.decrypt(&sk, &evaluator.rotate(&ct0, *r, &ek)),
+                    Encoding::default(),
+                )
+            })
+            .collect_vec();
+
+        for level in [1, 2, 4] {
+            let mut ct0_level = ct0.clone();
+            let mut ct1_level = ct1.clone();
+            evaluator.mod_down_level(&mut ct0_level, level);
+            evaluator.mod_down_level(&mut ct1_level, level);
+
+            let ct01 = evaluator.relinearize(&evaluator.mul(&ct0_level, &ct1_level), &ek);
+            assert!(ct01.level() == level);
+            let res_m =
+                evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct01), Encoding::default());
+            assert_eq!(res_m, m0m1);
+
+            let rotated = evaluator.rotate_many(&ct0_level, &[1, 3], &ek);
+            izip!(rotated.iter(), expected_rotations.iter()).for_each(|(ct, expected)| {
+                assert!(ct.level() == level);
+                let res_m =
+                    evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default());
+                assert_eq!(&res_m, expected);
+            });
+        }
+    }
+
+    #[test]
+    fn key_switching_methods_work() {
+        let mut rng = thread_rng();
+
+        // alpha = 1, dnum = 1 and BV
+        let mut alpha_1 = BfvParameters::new(&[50; 5], 65537, 1 << 4);
+        alpha_1.enable_hybrid_key_switching_with_alpha(1, &[50]);
+        let mut dnum_1 = BfvParameters::new(&[50; 5], 65537, 1 << 4);
+        dnum_1.enable_hybrid_key_switching_with_alpha(5, &[50; 5]);
+        assert!(dnum_1.dnum == Some(1));
+        let mut bv = BfvParameters::new(&[50; 5], 65537, 1 << 4);
+        bv.enable_bv_key_switching();
+
+        for params in [alpha_1, dnum_1, bv] {
+            let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+            let ek = EvaluationKey::new(&params, &sk, &[0], &[0, 0], &[1, 2], &mut rng);
+
+            let m0 = params
+                .plaintext_modulus_op
+                .random_vec(params.degree, &mut rng);
+            let m1 = params
+                .plaintext_modulus_op
+                .random_vec(params.degree, &mut rng);
+            let mut m0m1 = m0.clone();
+            params.plaintext_modulus_op.mul_mod_fast_vec(&mut m0m1, &m1);
+
+            let evaluator = Evaluator::new(params);
+            let pt0 = evaluator.plaintext_encode(&m0, Encoding::default());
+            let pt1 = evaluator.plaintext_encode(&m1, Encoding::default());
+            let ct0 = evaluator.encrypt(&sk, &pt0, &mut rng);
+            let ct1 = evaluator.encrypt(&sk, &pt1, &mut rng);
+
+            for level in [0, 1] {
+                let mut ct0_level = ct0.clone();
+                let mut ct1_level = ct1.clone();
+                evaluator.mod_down_level(&mut ct0_level, level);
+                evaluator.mod_down_level(&mut ct1_level, level);
+
+                let ct01 = evaluator.relinearize(&evaluator.mul(&ct0_level, &ct1_level), &ek);
+                let res_m =
+                    evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct01), Encoding::default());
+                assert_eq!(res_m, m0m1);
+
+                // rotating by 1 twice must equal rotating by 2
+                let rotated = evaluator.rotate_many(&ct0_level, &[1, 2], &ek);
+                let expected = evaluator.plaintext_decode(
+                    &evaluator.decrypt(&sk, &evaluator.rotate(&rotated[0], 1, &ek)),
+                    Encoding::default(),
+                );
+                let res_m = evaluator
+                    .plaintext_decode(&evaluator.decrypt(&sk, &rotated[1]), Encoding::default());
+                assert_eq!(res_m, expected);
+                assert_ne!(res_m, m0);
+            }
+        }
+    }
+
+    #[test]
+    fn rotation_key_policy_works() {
+        let mut rng = thread_rng();
+        let mut params = BfvParameters::new(&[50; 3], 65537, 1 << 5);
+        params.enable_hybrid_key_switching(&[50, 50, 50]);
+
+        let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+        let row_size = (params.degree / 2) as isize;
+        let ek = EvaluationKey::with_policy(
+            &params,
+            &sk,
+            &[],
+            0,
+            &RotationKeyPolicy::PowersOfTwo,
+            &mut rng,
+        );
+        // reference keys for every rotation
+        let all_indices = (1..row_size).collect_vec();
+        let ek_all = EvaluationKey::with_policy(
+            &params,
+            &sk,
+            &[],
+            0,
+            &RotationKeyPolicy::Explicit(all_indices.clone()),
+            &mut rng,
+        );
+
+        let m0 = params
+            .plaintext_modulus_op
+            .random_vec(params.degree, &mut rng);
+        let evaluator = Evaluator::new(params);
+        let pt0 = evaluator.plaintext_encode(&m0, Encoding::default());
+        let ct0 = evaluator.encrypt(&sk, &pt0, &mut rng);
+
+        let decrypt = |ct: &Ciphertext| {
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default())
+        };
+
+        let hoisted = evaluator.rotate_many(&ct0, &all_indices, &ek);
+        izip!(all_indices.iter(), hoisted.iter()).for_each(|(r, ct)| {
+            let expected = decrypt(&evaluator.rotate(&ct0, *r, &ek_all));
+            assert_eq!(decrypt(&evaluator.rotate(&ct0, *r, &ek)), expected);
+            assert_eq!(
+                decrypt(&evaluator.rotate(&ct0, *r - row_size, &ek)),
+                expected
+            );
+            assert_eq!(decrypt(ct), expected);
+
+            // every amount modulo row size 16 is sum of at most 2 powers of two (up to sign)
+            let steps = ek.rotation_steps(*r, evaluator.params().degree, 0).unwrap();
+            assert!(steps.len() <= 2);
+        });
+
+        assert_eq!(decrypt(&evaluator.rotate(&ct0, 0, &ek)), m0);
+        assert!(ek_all
+            .rotation_steps(2 * evaluator.params().degree as isize - 1, 32, 0)
+            .is_none());
+    }
+
     #[test]
     #[ignore = "Takes long because degree is set to 2^15"]
     fn test_mul_lazy_add_and_relinearize() {
@@ -679,24 +1041,64 @@ mod tests {
     }
 
     #[test]
-    fn add_noise_works() {
+    fn sanitize_works() {
         let mut rng = thread_rng();
         let params = BfvParameters::default(15, 1 << 4);
 
         let sk = SecretKey::random(params.degree, params.hw, &mut rng);
-        let mut m0 = params
+        let pk = PublicKey::new(&params, &sk, &mut rng);
+        let m0 = params
             .plaintext_modulus_op
             .random_vec(params.degree, &mut rng);
 
         let evaluator = Evaluator::new(params);
         let pt0 = evaluator.plaintext_encode(&m0, Encoding::default());
-        let mut ct = evaluator.encrypt(&sk, &pt0, &mut rng);
+        let ct = evaluator.encrypt(&sk, &pt0, &mut rng);
 
-        println!("Noise before: {}", evaluator.measure_noise(&sk, &ct));
-        unsafe {
-            evaluator.add_noise(&mut ct, 100);
-        }
-        println!("Noise after: {}", evaluator.measure_noise(&sk, &ct));
+        let bound = evaluator.sanitize_noise_bound(ct.level());
+        assert!(evaluator.measure_noise(&sk, &ct) <= bound);
+
+        let ct_sanitized = evaluator.sanitize(&ct, &pk, &mut rng);
+        assert_ne!(ct_sanitized, ct);
+        assert!(
+            evaluator.measure_noise(&sk, &ct_sanitized)
+                >= bound + FLOODING_STATISTICAL_SECURITY as u64 - 1
+        );
+        assert_eq!(
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct_sanitized), Encoding::default()),
+            m0
+        );
+
+        // flooding sized from noise estimate leaves noise budget for computation
+        let noise_bits = evaluator.measure_noise(&sk, &ct);
+        let ct_sanitized = evaluator.sanitize_with_noise_bits(&ct, &pk, noise_bits, &mut rng);
+        let noise_sanitized = evaluator.measure_noise(&sk, &ct_sanitized);
+        assert!(noise_sanitized >= noise_bits + FLOODING_STATISTICAL_SECURITY as u64 - 1);
+        assert!(noise_sanitized < bound);
+        let ct_double = evaluator.add(&ct_sanitized, &ct_sanitized);
+        assert_eq!(
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct_double), Encoding::default()),
+            m0.iter()
+                .map(|v| evaluator.params().plaintext_modulus_op.add_mod_fast(*v, *v))
+                .collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn sanitize_panics_if_flooding_exceeds_noise_budget() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(3, 1 << 4);
+
+        let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+        let pk = PublicKey::new(&params, &sk, &mut rng);
+        let evaluator = Evaluator::new(params);
+        let pt =
+            evaluator.plaintext_encode(&vec![0; evaluator.params().degree], Encoding::default());
+        let ct = evaluator.encrypt(&sk, &pt, &mut rng);
+
+        let noise_bits = evaluator.noise_budget_bits(ct.level());
+        evaluator.sanitize_with_noise_bits(&ct, &pk, noise_bits, &mut rng);
     }
 
     #[test]
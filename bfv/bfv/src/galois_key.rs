@@ -1,13 +1,15 @@
 use crate::{
-    BfvParameters, Ciphertext, HybridKeySwitchingKey, Modulus, Poly, PolyContext, PolyType,
-    Representation, SecretKey, Substitution,
+    BfvParameters, Ciphertext, KeySwitchingKey, Poly, PolyType, Representation, SecretKey,
+    Substitution,
 };
-use rand::{CryptoRng, RngCore};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use zeroize::Zeroizing;
 
 #[derive(Debug, PartialEq)]
 pub struct GaloisKey {
     pub(crate) substitution: Substitution,
-    pub(crate) ksk_key: HybridKeySwitchingKey,
+    pub(crate) ksk_key: KeySwitchingKey,
     pub(crate) level: usize,
 }
 
@@ -18,28 +20,32 @@ impl GaloisKey {
         level: usize,
         sk: &SecretKey,
         rng: &mut R,
+    ) -> GaloisKey {
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        rng.fill_bytes(&mut seed);
+        GaloisKey::new_with_seed(exponent, params, level, sk, seed, rng)
+    }
+
+    /// Same as `new` except that key switching key is generated from `seed` (see
+    /// `KeySwitchingKey::new_with_seed`)
+    pub fn new_with_seed<R: CryptoRng + RngCore>(
+        exponent: usize,
+        params: &BfvParameters,
+        level: usize,
+        sk: &SecretKey,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+        rng: &mut R,
     ) -> GaloisKey {
         let substitution = Substitution::new(exponent, params.degree);
 
         let q_ctx = params.poly_ctx(&PolyType::Q, level);
-        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
 
         // Substitute secret key
-        let mut sk_poly =
-            q_ctx.try_convert_from_i64_small(&sk.coefficients, Representation::Coefficient);
-        q_ctx.change_representation(&mut sk_poly, Representation::Evaluation);
-
-        let sk_poly = q_ctx.substitute(&sk_poly, &substitution);
+        let sk_poly = sk.to_poly(&q_ctx);
+        let sk_poly = Zeroizing::new(q_ctx.substitute(&sk_poly, &substitution));
 
         // Generate key switching key for substituted secret key
-        let ksk_key = HybridKeySwitchingKey::new(
-            params.hybrid_key_switching_params_at_level(level),
-            &sk_poly,
-            &sk,
-            &qp_ctx,
-            params.variance,
-            rng,
-        );
+        let ksk_key = KeySwitchingKey::new_with_seed(params, &sk_poly, &sk, level, seed, rng);
 
         GaloisKey {
             substitution,
@@ -50,13 +56,11 @@ impl GaloisKey {
 
     pub fn rotate(&self, ct: &Ciphertext, params: &BfvParameters) -> Ciphertext {
         assert!(ct.c.len() == 2);
-        assert!(ct.level == self.level);
+        assert!(ct.level >= self.level);
         assert!(ct.poly_type == PolyType::Q);
 
-        let level = self.level;
+        let level = ct.level;
         let q_ctx = params.poly_ctx(&PolyType::Q, level);
-        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
-        let specialp_ctx = params.poly_ctx(&PolyType::SpecialP, level);
 
         // Key switch c1
         let mut c1 = q_ctx.substitute(&ct.c[1], &self.substitution);
@@ -64,14 +68,52 @@ impl GaloisKey {
             q_ctx.change_representation(&mut c1, Representation::Coefficient);
         }
 
-        let (mut cs0, mut cs1) = self.ksk_key.switch(
-            params.hybrid_key_switching_params_at_level(level),
-            &c1,
-            &qp_ctx,
-            &q_ctx,
-            &specialp_ctx,
+        let (cs0, cs1) = self.ksk_key.switch(params, &c1, level);
+        self.finish_rotation(ct, cs0, cs1, params)
+    }
+
+    /// Rotates `ct` given decomposition of its `c1` (see `KeySwitchingKey::decompose`).
+    ///
+    /// Substitution commutes with decomposition (it only permutes and negates coefficients), thus
+    /// decomposed parts of `c1` can be substituted directly instead of decomposing substituted
+    /// `c1`. This allows to share single decomposition among several rotations of the same
+    /// ciphertext.
+    pub fn rotate_decomposed(
+        &self,
+        ct: &Ciphertext,
+        decomposed: &[Poly],
+        params: &BfvParameters,
+    ) -> Ciphertext {
+        assert!(ct.c.len() == 2);
+        assert!(ct.level >= self.level);
+        assert!(ct.poly_type == PolyType::Q);
+
+        let level = ct.level;
+        let decomposition_ctx = params.poly_ctx(
+            &KeySwitchingKey::decomposition_poly_type(self.ksk_key.method()),
+            level,
         );
 
+        let substituted = decomposed
+            .iter()
+            .map(|p| decomposition_ctx.substitute(p, &self.substitution))
+            .collect::<Vec<Poly>>();
+        let (cs0, cs1) = self.ksk_key.switch_decomposed(params, &substituted, level);
+        self.finish_rotation(ct, cs0, cs1, params)
+    }
+
+    /// Returns `(cs0 + substituted c0, cs1)` in representation of `ct`, where `(cs0, cs1)` is key
+    /// switched substituted `c1` in `Evaluation` representation.
+    fn finish_rotation(
+        &self,
+        ct: &Ciphertext,
+        mut cs0: Poly,
+        mut cs1: Poly,
+        params: &BfvParameters,
+    ) -> Ciphertext {
+        let level = ct.level;
+        let q_ctx = params.poly_ctx(&PolyType::Q, level);
+
         // Key switch returns polynomial in Evaluation form
         if ct.c[0].representation != cs0.representation {
             q_ctx.change_representation(&mut cs0, ct.c[0].representation.clone());
@@ -0,0 +1,209 @@
+use crate::evaluation_key::rtg_galois_element;
+use crate::{BfvParameters, EvaluationKey, GaloisKey, RelinearizationKey, SecretKey};
+use itertools::izip;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashMap;
+use zeroize::Zeroize;
+
+/// Purpose of randomness derived from master seed. Every domain maps to a distinct ChaCha20
+/// stream, thus randomness of different domains is independent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyDomain {
+    SecretKey,
+    RelinearizationKey {
+        level: usize,
+    },
+    GaloisKey {
+        rot_by: isize,
+        level: usize,
+    },
+    /// Randomness of `nonce^th` encryption. Reusing nonce for different plaintexts is insecure.
+    Encryption {
+        nonce: u64,
+    },
+}
+
+impl KeyDomain {
+    /// Returns ChaCha20 stream id of the domain: tag of the domain in the top byte followed by
+    /// 56 bits of domain parameters.
+    fn stream(&self) -> u64 {
+        let (tag, value) = match *self {
+            KeyDomain::SecretKey => (0u64, 0u64),
+            KeyDomain::RelinearizationKey { level } => {
+                assert!(level < (1 << 24));
+                (1, level as u64)
+            }
+            KeyDomain::GaloisKey { rot_by, level } => {
+                assert!(level < (1 << 24));
+                assert!(i32::try_from(rot_by).is_ok());
+                (2, ((level as u64) << 32) | (rot_by as i32 as u32 as u64))
+            }
+            KeyDomain::Encryption { nonce } => {
+                assert!(nonce < (1 << 56));
+                (3, nonce)
+            }
+        };
+        (tag << 56) | value
+    }
+}
+
+/// Derives keys deterministically from a 32 bytes master seed.
+///
+/// Each key is generated with randomness of its own domain (see `KeyDomain`), thus any single key
+/// can be regenerated independently of the others and keys do not depend on order of generation.
+/// For ex, server that knows master seed can regenerate evaluation keys instead of storing them.
+pub struct KeyDerivation {
+    master_seed: [u8; 32],
+}
+
+impl Drop for KeyDerivation {
+    fn drop(&mut self) {
+        self.master_seed.zeroize();
+    }
+}
+
+impl KeyDerivation {
+    pub fn new(master_seed: [u8; 32]) -> KeyDerivation {
+        KeyDerivation { master_seed }
+    }
+
+    /// Returns rng of `domain`
+    pub fn rng(&self, domain: KeyDomain) -> ChaCha20Rng {
+        let mut rng = ChaCha20Rng::from_seed(self.master_seed);
+        rng.set_stream(domain.stream());
+        rng
+    }
+
+    pub fn secret_key(&self, params: &BfvParameters) -> SecretKey {
+        SecretKey::random_with_params(params, &mut self.rng(KeyDomain::SecretKey))
+    }
+
+    pub fn relinearization_key(
+        &self,
+        params: &BfvParameters,
+        sk: &SecretKey,
+        level: usize,
+    ) -> RelinearizationKey {
+        let mut rng = self.rng(KeyDomain::RelinearizationKey { level });
+        RelinearizationKey::new(params, sk, level, &mut rng)
+    }
+
+    /// Returns Galois key for rotation by `rot_by` (`2 * degree - 1` for row swap)
+    pub fn galois_key(
+        &self,
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rot_by: isize,
+        level: usize,
+    ) -> GaloisKey {
+        let mut rng = self.rng(KeyDomain::GaloisKey { rot_by, level });
+        GaloisKey::new(
+            rtg_galois_element(rot_by, params.degree),
+            params,
+            level,
+            sk,
+            &mut rng,
+        )
+    }
+
+    /// Derives `EvaluationKey` with same keys as `EvaluationKey::new`
+    pub fn evaluation_key(
+        &self,
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rlk_levels: &[usize],
+        rtg_levels: &[usize],
+        rtg_indices: &[isize],
+    ) -> EvaluationKey {
+        assert!(rtg_levels.len() == rtg_indices.len());
+
+        let mut rlks = HashMap::new();
+        rlk_levels.iter().for_each(|l| {
+            rlks.insert(*l, self.relinearization_key(params, sk, *l));
+        });
+
+        let mut rtgs = HashMap::new();
+        izip!(rtg_indices.iter(), rtg_levels.iter()).for_each(|(index, level)| {
+            rtgs.insert(
+                (*index, *level),
+                self.galois_key(params, sk, *index, *level),
+            );
+        });
+
+        EvaluationKey { rlks, rtgs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, Evaluator};
+    use rand::{thread_rng, RngCore};
+
+    #[test]
+    fn key_derivation_works() {
+        let mut rng = thread_rng();
+        let mut params = BfvParameters::new(&[50; 3], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[50; 3]);
+
+        let mut master_seed = [0u8; 32];
+        rng.fill_bytes(&mut master_seed);
+        let kd = KeyDerivation::new(master_seed);
+
+        let sk = kd.secret_key(&params);
+        assert_eq!(sk, KeyDerivation::new(master_seed).secret_key(&params));
+
+        let row_swap = (2 * params.degree - 1) as isize;
+        let ek = kd.evaluation_key(&params, &sk, &[0, 1], &[0, 0, 1], &[1, row_swap, 1]);
+        // keys are independent of order of generation
+        let ek_other = KeyDerivation::new(master_seed).evaluation_key(
+            &params,
+            &sk,
+            &[1, 0],
+            &[1, 0, 0],
+            &[1, row_swap, 1],
+        );
+        assert_eq!(ek, ek_other);
+        assert_eq!(ek.get_rtg(1, 0), Some(&kd.galois_key(&params, &sk, 1, 0)));
+
+        // domains are separated
+        assert_ne!(
+            kd.galois_key(&params, &sk, 1, 0),
+            kd.galois_key(&params, &sk, 1, 1)
+        );
+        assert_ne!(
+            kd.galois_key(&params, &sk, 1, 0),
+            kd.galois_key(&params, &sk, -1, 0)
+        );
+        assert_ne!(sk, KeyDerivation::new([0u8; 32]).secret_key(&params));
+
+        // derived keys work and encryptions are reproducible
+        let evaluator = Evaluator::new(params);
+        let m = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(evaluator.params().degree, &mut rng);
+        let pt = evaluator.plaintext_encode(&m, Encoding::default());
+        let ct = evaluator.encrypt(&sk, &pt, &mut kd.rng(KeyDomain::Encryption { nonce: 0 }));
+        assert_eq!(
+            ct,
+            evaluator.encrypt(&sk, &pt, &mut kd.rng(KeyDomain::Encryption { nonce: 0 }))
+        );
+
+        let ct_sq = evaluator.relinearize(&evaluator.mul(&ct, &ct), &ek);
+        let ct_rot = evaluator.rotate(&ct_sq, 1, &ek);
+        let res = evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct_rot), Encoding::default());
+
+        let modt = &evaluator.params().plaintext_modulus_op;
+        let row_size = evaluator.params().degree / 2;
+        let expected = (0..evaluator.params().degree)
+            .map(|i| {
+                let row = i / row_size;
+                let j = row * row_size + (i % row_size + 1) % row_size;
+                modt.mul_mod_fast(m[j], m[j])
+            })
+            .collect::<Vec<u64>>();
+        assert_eq!(res, expected);
+    }
+}
@@ -1,21 +1,194 @@
 use crate::modulus::Modulus;
 use crate::{mod_inverse_biguint, mod_inverse_biguint_u64};
 use crate::{
-    secret_key::SecretKey, HybridKeySwitchingParameters, Poly, PolyContext, Representation,
+    secret_key::SecretKey, BfvParameters, HybridKeySwitchingParameters, KeySwitchingMethod, Poly,
+    PolyContext, PolyType, Representation,
 };
 use crypto_bigint::rand_core::CryptoRngCore;
 use itertools::{izip, Itertools};
 use ndarray::{azip, s, Array1, Array2, Array3, Axis, IntoNdProducer};
 use num_bigint::{BigUint, ToBigInt};
 use num_traits::{FromPrimitive, One, ToPrimitive};
-use rand::{CryptoRng, SeedableRng};
+use rand::{CryptoRng, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
+use std::borrow::Cow;
 use std::default;
 use traits::Ntt;
-struct BVKeySwitchingKey {
-    c0s: Box<[Poly]>,
-    c1s: Box<[Poly]>,
-    seed: <ChaCha8Rng as SeedableRng>::Seed,
+use zeroize::{Zeroize, Zeroizing};
+/// Key switching key of either method (see `KeySwitchingMethod`)
+#[derive(Debug, PartialEq)]
+pub enum KeySwitchingKey {
+    Hybrid(HybridKeySwitchingKey),
+    BV(BVKeySwitchingKey),
+}
+
+impl KeySwitchingKey {
+    /// Generates key that switches `poly` to `sk` using `params.key_switching_method`. `poly` must
+    /// be in `Evaluation` representation in Q basis at `level`.
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        poly: &Poly,
+        sk: &SecretKey,
+        level: usize,
+        rng: &mut R,
+    ) -> KeySwitchingKey {
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        rng.fill_bytes(&mut seed);
+        KeySwitchingKey::new_with_seed(params, poly, sk, level, seed, rng)
+    }
+
+    /// Same as `new` except that c1s are generated from `seed`. Keys generated by several parties
+    /// from a common `seed` share c1s and can be aggregated (see `GaloisKey::aggregate`).
+    pub fn new_with_seed<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        poly: &Poly,
+        sk: &SecretKey,
+        level: usize,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+        rng: &mut R,
+    ) -> KeySwitchingKey {
+        match params.key_switching_method {
+            KeySwitchingMethod::Hybrid => {
+                KeySwitchingKey::Hybrid(HybridKeySwitchingKey::new_with_seed(
+                    params.hybrid_key_switching_params_at_level(level),
+                    poly,
+                    sk,
+                    &params.poly_ctx(&PolyType::QP, level),
+                    params.variance,
+                    seed,
+                    rng,
+                ))
+            }
+            KeySwitchingMethod::BV => KeySwitchingKey::BV(BVKeySwitchingKey::new_with_seed(
+                poly,
+                sk,
+                &params.poly_ctx(&PolyType::Q, level),
+                params.variance,
+                seed,
+                rng,
+            )),
+        }
+    }
+
+    /// Returns sum of `keys` generated with the same seed. Since c1s are common, sum of c0s
+    /// switches sum of polynomials to sum of secret keys.
+    pub(crate) fn aggregate(
+        keys: Vec<KeySwitchingKey>,
+        params: &BfvParameters,
+        level: usize,
+    ) -> Self {
+        let mut keys = keys.into_iter();
+        let mut sum = keys.next().expect("No keys to aggregate");
+        let ctx = params.poly_ctx(&Self::decomposition_poly_type(sum.method()), level);
+
+        keys.for_each(|k| {
+            let (c0s, other_c0s) = match (&mut sum, &k) {
+                (KeySwitchingKey::Hybrid(s), KeySwitchingKey::Hybrid(k)) => {
+                    assert!(s.seed.is_some() && s.seed == k.seed);
+                    (&mut s.c0s, &k.c0s)
+                }
+                (KeySwitchingKey::BV(s), KeySwitchingKey::BV(k)) => {
+                    assert!(s.seed == k.seed);
+                    (&mut s.c0s, &k.c0s)
+                }
+                _ => panic!("Keys use different key switching methods"),
+            };
+            izip!(c0s.iter_mut(), other_c0s.iter()).for_each(|(c0, other)| {
+                ctx.add_assign(c0, other);
+            });
+        });
+        sum
+    }
+
+    pub fn method(&self) -> KeySwitchingMethod {
+        match self {
+            KeySwitchingKey::Hybrid(_) => KeySwitchingMethod::Hybrid,
+            KeySwitchingKey::BV(_) => KeySwitchingMethod::BV,
+        }
+    }
+
+    /// Returns poly type of parts returned by `decompose` for `method`
+    pub fn decomposition_poly_type(method: KeySwitchingMethod) -> PolyType {
+        match method {
+            KeySwitchingMethod::Hybrid => PolyType::QP,
+            KeySwitchingMethod::BV => PolyType::Q,
+        }
+    }
+
+    /// Decomposes `poly` in Q basis at `level` for key switching with
+    /// `params.key_switching_method`. `poly` must be in `Coefficient` representation.
+    pub fn decompose(params: &BfvParameters, poly: &Poly, level: usize) -> Vec<Poly> {
+        match params.key_switching_method {
+            KeySwitchingMethod::Hybrid => HybridKeySwitchingKey::decompose(
+                params.hybrid_key_switching_params_at_level(level),
+                poly,
+                &params.poly_ctx(&PolyType::QP, level),
+            ),
+            KeySwitchingMethod::BV => {
+                BVKeySwitchingKey::decompose(poly, &params.poly_ctx(&PolyType::Q, level))
+            }
+        }
+    }
+
+    /// Key switches polynomial decomposed with `decompose` at `level`. Returns `(c0, c1)` in Q
+    /// basis at `level` and `Evaluation` representation.
+    pub fn switch_decomposed(
+        &self,
+        params: &BfvParameters,
+        decomposed: &[Poly],
+        level: usize,
+    ) -> (Poly, Poly) {
+        assert!(self.method() == params.key_switching_method);
+
+        match self {
+            KeySwitchingKey::Hybrid(ksk) => ksk.switch_decomposed(
+                params.hybrid_key_switching_params_at_level(level),
+                decomposed,
+                &params.poly_ctx(&PolyType::QP, level),
+                &params.poly_ctx(&PolyType::Q, level),
+                &params.poly_ctx(&PolyType::SpecialP, level),
+            ),
+            KeySwitchingKey::BV(ksk) => {
+                ksk.switch_decomposed(decomposed, &params.poly_ctx(&PolyType::Q, level))
+            }
+        }
+    }
+
+    /// Key switches `poly` in Q basis at `level` and `Coefficient` representation. Returns
+    /// `(c0, c1)` in `Evaluation` representation.
+    pub fn switch(&self, params: &BfvParameters, poly: &Poly, level: usize) -> (Poly, Poly) {
+        let decomposed = Self::decompose(params, poly, level);
+        self.switch_decomposed(params, &decomposed, level)
+    }
+}
+
+/// Returns key polynomial `poly` generated at a lower level (ie with more moduli in Q) restricted
+/// to moduli of `ctx`. Special moduli, if any, are always the last rows.
+///
+/// Since Q at any level is a prefix of Q at lower levels and gadget values are 0 modulo every
+/// modulus outside of their own part, restricted key is a valid key at level of `ctx`.
+pub(crate) fn drop_moduli<'a>(poly: &'a Poly, ctx: &PolyContext<'_>) -> Cow<'a, Poly> {
+    let rows = poly.coefficients.shape()[0];
+    if rows == ctx.moduli_count {
+        return Cow::Borrowed(poly);
+    }
+    assert!(rows > ctx.moduli_count);
+
+    let q_count = ctx.moduli_ops.0.len();
+    let p_count = ctx.moduli_ops.1.len();
+    let indices = (0..q_count).chain((rows - p_count)..rows).collect_vec();
+    Cow::Owned(Poly::new(
+        poly.coefficients.select(Axis(0), &indices),
+        poly.representation.clone(),
+    ))
+}
+
+/// BV key switching key. Decomposes polynomial into its residues modulo every `qi`.
+#[derive(Debug, PartialEq)]
+pub struct BVKeySwitchingKey {
+    pub(crate) c0s: Box<[Poly]>,
+    pub(crate) c1s: Box<[Poly]>,
+    pub(crate) seed: <ChaCha8Rng as SeedableRng>::Seed,
 }
 
 impl BVKeySwitchingKey {
@@ -23,16 +196,29 @@ impl BVKeySwitchingKey {
         poly: &Poly,
         sk: &SecretKey,
         ksk_ctx: &PolyContext<'_>,
+        variance: usize,
+        rng: &mut R,
+    ) -> BVKeySwitchingKey {
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        rng.fill_bytes(&mut seed);
+        Self::new_with_seed(poly, sk, ksk_ctx, variance, seed, rng)
+    }
+
+    /// Same as `new` except that c1s are generated from `seed`
+    pub fn new_with_seed<R: CryptoRng + CryptoRngCore>(
+        poly: &Poly,
+        sk: &SecretKey,
+        ksk_ctx: &PolyContext<'_>,
+        variance: usize,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
         rng: &mut R,
     ) -> BVKeySwitchingKey {
         // check that ciphertext context has more than on moduli, otherwise key switching does not makes sense
         debug_assert!(ksk_ctx.moduli_count > 1);
 
         // c1s
-        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
-        rng.fill_bytes(&mut seed);
-        let c1s = Self::generate_c1(ksk_ctx, seed);
-        let c0s = Self::generate_c0(ksk_ctx, poly, &c1s, sk, rng);
+        let c1s = Self::generate_c1(ksk_ctx.moduli_count, ksk_ctx, seed);
+        let c0s = Self::generate_c0(ksk_ctx, poly, &c1s, sk, variance, rng);
 
         BVKeySwitchingKey {
             c0s: c0s.into_boxed_slice(),
@@ -41,48 +227,60 @@ impl BVKeySwitchingKey {
         }
     }
 
-    pub fn switch(&self, poly: &Poly, ksk_ctx: &PolyContext<'_>) -> (Poly, Poly) {
-        // TODO: check that poly matches ksk_ctx
-        // And ksk_ctx matches the key
+    /// Returns residues of `poly` modulo every `qi` in `ksk_ctx`, each lifted to `ksk_ctx`, in
+    /// `Evaluation` representation.
+    pub fn decompose(poly: &Poly, ksk_ctx: &PolyContext<'_>) -> Vec<Poly> {
         debug_assert!(poly.representation == Representation::Coefficient);
+        debug_assert!(poly.coefficients.shape()[0] == ksk_ctx.moduli_count);
+
+        poly.coefficients
+            .outer_iter()
+            .map(|residues| {
+                let mut p = ksk_ctx.try_convert_from_u64(
+                    residues.as_slice().unwrap(),
+                    Representation::Coefficient,
+                );
+                ksk_ctx.change_representation(&mut p, Representation::Evaluation);
+                p
+            })
+            .collect_vec()
+    }
 
-        let mut p = ksk_ctx.try_convert_from_u64(
-            poly.coefficients.slice(s![0, ..]).as_slice().unwrap(),
-            Representation::Coefficient,
-        );
-        ksk_ctx.change_representation(&mut p, Representation::Evaluation);
-        let mut c1_out = ksk_ctx.mul(&self.c1s[0], &p);
-        ksk_ctx.mul_assign(&mut p, &self.c0s[0]);
-        let mut c0_out = p;
-
-        izip!(
-            self.c0s.iter(),
-            self.c1s.iter(),
-            poly.coefficients.outer_iter()
-        )
-        .skip(1)
-        .for_each(|(c0, c1, rests)| {
-            let mut p = ksk_ctx
-                .try_convert_from_u64(rests.as_slice().unwrap(), Representation::Coefficient);
-            ksk_ctx.change_representation(&mut p, Representation::Evaluation);
-
-            ksk_ctx.add_assign(&mut c1_out, &ksk_ctx.mul(c1, &p));
-            ksk_ctx.mul_assign(&mut p, &c0);
-            ksk_ctx.add_assign(&mut c0_out, &p);
+    /// Key switches polynomial decomposed with `decompose`. Key may have been generated at a lower
+    /// level than `ksk_ctx` (see `drop_moduli`).
+    pub fn switch_decomposed(
+        &self,
+        decomposed: &[Poly],
+        ksk_ctx: &PolyContext<'_>,
+    ) -> (Poly, Poly) {
+        assert!(decomposed.len() <= self.c0s.len());
+
+        let mut c0_out = ksk_ctx.zero(Representation::Evaluation);
+        let mut c1_out = ksk_ctx.zero(Representation::Evaluation);
+        izip!(decomposed.iter(), self.c0s.iter(), self.c1s.iter()).for_each(|(p, c0, c1)| {
+            ksk_ctx.add_assign(&mut c0_out, &ksk_ctx.mul(p, &drop_moduli(c0, ksk_ctx)));
+            ksk_ctx.add_assign(&mut c1_out, &ksk_ctx.mul(p, &drop_moduli(c1, ksk_ctx)));
         });
 
         (c0_out, c1_out)
     }
 
+    pub fn switch(&self, poly: &Poly, ksk_ctx: &PolyContext<'_>) -> (Poly, Poly) {
+        self.switch_decomposed(&Self::decompose(poly, ksk_ctx), ksk_ctx)
+    }
+
+    /// Generates `count` polynomials from the seed and returns them in `Evaluation` representation
     pub fn generate_c1(
+        count: usize,
         ksk_ctx: &PolyContext<'_>,
         seed: <ChaCha8Rng as SeedableRng>::Seed,
     ) -> Vec<Poly> {
         let mut rng = ChaCha8Rng::from_seed(seed);
-        (0..ksk_ctx.moduli_count)
-            .into_iter()
+        (0..count)
             .map(|_| {
-                let mut p = ksk_ctx.random_with_seed(seed);
+                // sampled in `Coefficient` representation, so that key is independent of `Ntt`
+                // backend
+                let mut p = ksk_ctx.random(Representation::Coefficient, &mut rng);
                 ksk_ctx.change_representation(&mut p, Representation::Evaluation);
                 p
             })
@@ -94,13 +292,12 @@ impl BVKeySwitchingKey {
         poly: &Poly,
         c1s: &[Poly],
         sk: &SecretKey,
+        variance: usize,
         rng: &mut R,
     ) -> Vec<Poly> {
         debug_assert!(poly.representation == Representation::Evaluation);
 
-        let mut sk =
-            ksk_ctx.try_convert_from_i64_small(&sk.coefficients, Representation::Coefficient);
-        ksk_ctx.change_representation(&mut sk, Representation::Evaluation);
+        let sk = sk.to_poly(ksk_ctx);
 
         // gi = (q/qi) * [(q/qi)^-1]_qi
         let big_q = ksk_ctx.big_q();
@@ -115,20 +312,20 @@ impl BVKeySwitchingKey {
 
         izip!(g.into_iter(), c1s.iter())
             .map(|(g, c1)| {
-                let mut g = ksk_ctx.try_convert_from_biguint(
+                let mut g = Zeroizing::new(ksk_ctx.try_convert_from_biguint(
                     vec![g.clone(); ksk_ctx.degree].as_slice(),
                     Representation::Evaluation,
-                );
+                ));
                 // m = gi*poly
                 ksk_ctx.mul_assign(&mut g, &poly);
 
-                let mut e = ksk_ctx.random_gaussian(Representation::Coefficient, 10, rng);
+                let mut e = ksk_ctx.random_gaussian(Representation::Coefficient, variance, rng);
                 ksk_ctx.change_representation(&mut e, Representation::Evaluation);
                 // m + e
                 ksk_ctx.add_assign(&mut e, &g);
 
                 // -a*sk
-                let c1_sk = ksk_ctx.mul(&c1, &sk);
+                let c1_sk = Zeroizing::new(ksk_ctx.mul(&c1, &sk));
 
                 // m + e - a*sk
                 ksk_ctx.sub_assign(&mut e, &c1_sk);
@@ -164,7 +361,19 @@ impl HybridKeySwitchingKey {
     ) -> HybridKeySwitchingKey {
         let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
         rng.fill_bytes(&mut seed);
+        Self::new_with_seed(ksk_params, poly, sk, qp_ctx, variance, seed, rng)
+    }
 
+    /// Same as `new` except that c1s are generated from `seed`
+    pub fn new_with_seed<R: CryptoRng + CryptoRngCore>(
+        ksk_params: &HybridKeySwitchingParameters,
+        poly: &Poly,
+        sk: &SecretKey,
+        qp_ctx: &PolyContext<'_>,
+        variance: usize,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+        rng: &mut R,
+    ) -> HybridKeySwitchingKey {
         let mut c1s = Self::generate_c1(ksk_params.dnum, &qp_ctx, seed);
         // `generate_c1` returns polynomials in `Coefficient` repr but c1s are only used in `Evaluation` repr
         // so it is safe to convert them to `Evaluation`.
@@ -189,14 +398,26 @@ impl HybridKeySwitchingKey {
         ksk_ctx: &PolyContext<'_>,
         specialp_ctx: &PolyContext<'_>,
     ) -> (Poly, Poly) {
+        let decomposed = Self::decompose(ksk_params, poly, qp_ctx);
+        self.switch_decomposed(ksk_params, &decomposed, qp_ctx, ksk_ctx, specialp_ctx)
+    }
+
+    /// Divides `poly` into `dnum` parts of `alpha` moduli each and switches every part from Qj to
+    /// QP. Returns parts in `Evaluation` representation.
+    ///
+    /// Decomposition is the expensive half of key switching and does not depend on the key. Thus
+    /// it can be computed once and reused with several keys (see `switch_decomposed`).
+    pub fn decompose(
+        ksk_params: &HybridKeySwitchingParameters,
+        poly: &Poly,
+        qp_ctx: &PolyContext<'_>,
+    ) -> Vec<Poly> {
         // TODO: check poly context
         debug_assert!(poly.representation == Representation::Coefficient);
 
         let alpha = ksk_params.alpha;
 
-        // divide poly into parts and switch them from Qj to QP and key switch
-        let mut c0_out = Poly::placeholder();
-        let mut c1_out = Poly::placeholder();
+        let mut parts = Vec::with_capacity(ksk_params.dnum);
         for i in 0..ksk_params.dnum {
             let mut qp_poly = qp_ctx.zero(Representation::Coefficient);
 
@@ -271,15 +492,39 @@ impl HybridKeySwitchingKey {
             });
 
             qp_ctx.change_representation(&mut qp_poly, Representation::Evaluation);
+            parts.push(qp_poly);
+        }
+
+        parts
+    }
+
+    /// Key switches polynomial decomposed with `decompose`. Returns `(c0, c1)` in `Evaluation`
+    /// representation.
+    ///
+    /// Key may have been generated at a lower level (ie with more moduli in Q) than `qp_ctx` (see
+    /// `drop_moduli`). Key parts beyond `ksk_params.dnum` are ignored.
+    pub fn switch_decomposed(
+        &self,
+        ksk_params: &HybridKeySwitchingParameters,
+        decomposed: &[Poly],
+        qp_ctx: &PolyContext<'_>,
+        ksk_ctx: &PolyContext<'_>,
+        specialp_ctx: &PolyContext<'_>,
+    ) -> (Poly, Poly) {
+        assert!(decomposed.len() == ksk_params.dnum);
+        assert!(decomposed.len() <= self.c0s.len());
 
+        let mut c0_out = Poly::placeholder();
+        let mut c1_out = Poly::placeholder();
+        for (i, qp_poly) in decomposed.iter().enumerate() {
+            let c0 = drop_moduli(&self.c0s[i], qp_ctx);
+            let c1 = drop_moduli(&self.c1s[i], qp_ctx);
             if c1_out.representation == Representation::Unknown {
-                c1_out = qp_ctx.mul(&qp_poly, &self.c1s[i]);
-                qp_ctx.mul_assign(&mut qp_poly, &self.c0s[i]);
-                c0_out = qp_poly;
+                c1_out = qp_ctx.mul(qp_poly, &c1);
+                c0_out = qp_ctx.mul(qp_poly, &c0);
             } else {
-                qp_ctx.add_assign(&mut c1_out, &qp_ctx.mul(&qp_poly, &self.c1s[i]));
-                qp_ctx.mul_assign(&mut qp_poly, &self.c0s[i]);
-                qp_ctx.add_assign(&mut c0_out, &qp_poly);
+                qp_ctx.add_assign(&mut c1_out, &qp_ctx.mul(qp_poly, &c1));
+                qp_ctx.add_assign(&mut c0_out, &qp_ctx.mul(qp_poly, &c0));
             }
         }
 
@@ -311,9 +556,10 @@ impl HybridKeySwitchingKey {
         qp_ctx: &PolyContext<'_>,
         seed: <ChaCha8Rng as SeedableRng>::Seed,
     ) -> Vec<Poly> {
+        // every part must have its own c1, otherwise difference of any two c0s reveals the key
+        let mut rng = ChaCha8Rng::from_seed(seed);
         (0..count)
-            .into_iter()
-            .map(|_| qp_ctx.random_with_seed(seed))
+            .map(|_| qp_ctx.random(Representation::Coefficient, &mut rng))
             .collect_vec()
     }
 
@@ -338,7 +584,11 @@ impl HybridKeySwitchingKey {
         let c0s = izip!(c1s.iter(), g)
             .map(|(c1, g_part)| {
                 let mut c0 = qp_ctx.zero(Representation::Evaluation);
-                let mut e = qp_ctx.random_gaussian(Representation::Coefficient, variance, rng);
+                let mut e = Zeroizing::new(qp_ctx.random_gaussian(
+                    Representation::Coefficient,
+                    variance,
+                    rng,
+                ));
                 qp_ctx.change_representation(&mut e, Representation::Evaluation);
 
                 // Q
@@ -371,6 +621,7 @@ impl HybridKeySwitchingKey {
 
                     // [g * poly]_qi + [e]_qi - [c1s * sk]_qi
                     modqi.sub_mod_fast_vec(c0qi.as_slice_mut().unwrap(), &skqi);
+                    skqi.zeroize();
                 });
 
                 // P
@@ -393,6 +644,7 @@ impl HybridKeySwitchingKey {
                     nttpi.forward(&mut skpi);
                     modpi.mul_mod_fast_vec(&mut skpi, c1pi.as_slice().unwrap());
                     modpi.sub_mod_fast_vec(c0pi.as_slice_mut().unwrap(), &skpi);
+                    skpi.zeroize();
                 });
 
                 c0
@@ -419,7 +671,7 @@ mod tests {
         let sk = SecretKey::random(params.degree, params.hw, &mut rng);
 
         let poly = ksk_ctx.random(Representation::Evaluation, &mut rng);
-        let ksk = BVKeySwitchingKey::new(&poly, &sk, &ksk_ctx, &mut rng);
+        let ksk = BVKeySwitchingKey::new(&poly, &sk, &ksk_ctx, params.variance, &mut rng);
 
         let mut other_poly = ksk_ctx.random(Representation::Coefficient, &mut rng);
 
@@ -484,4 +736,23 @@ mod tests {
             dbg!(&diff_bits);
         });
     }
+
+    #[test]
+    fn hybrid_key_switching_key_c1s_are_distinct() {
+        let params = BfvParameters::default(5, 1 << 4);
+        let mut rng = thread_rng();
+        let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+        let poly = params
+            .poly_ctx(&PolyType::Q, 0)
+            .random(Representation::Evaluation, &mut rng);
+
+        let ksk = match KeySwitchingKey::new(&params, &poly, &sk, 0, &mut rng) {
+            KeySwitchingKey::Hybrid(ksk) => ksk,
+            _ => panic!("Expected hybrid key switching key"),
+        };
+        assert!(ksk.c1s.len() >= 2);
+        ksk.c1s.iter().tuple_combinations().for_each(|(a, b)| {
+            assert_ne!(a, b);
+        });
+    }
 }
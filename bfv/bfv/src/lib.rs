@@ -1,7 +1,9 @@
 mod ciphertext;
+mod decryption_proof;
 mod evaluation_key;
 mod evaluator;
 mod galois_key;
+mod key_derivation;
 mod key_switching_key;
 mod modulus;
 mod nb_theory;
@@ -9,30 +11,39 @@ mod ntt;
 mod parameters;
 mod plaintext;
 mod poly;
+mod public_key;
+mod re_encryption_key;
 mod relinearization_key;
 mod secret_key;
+mod threshold;
 mod utils;
 
 #[cfg(feature = "serialize")]
 mod proto;
 #[cfg(feature = "serialize")]
 pub use proto::proto::{
-    Ciphertext as CiphertextProto, EvaluationKey as EvaluationKeyProto, SecretKey as SecretKeyProto,
+    Ciphertext as CiphertextProto, DecryptionProof as DecryptionProofProto,
+    EvaluationKey as EvaluationKeyProto, SecretKey as SecretKeyProto,
 };
 
 pub use ciphertext::*;
+pub use decryption_proof::*;
 pub use evaluation_key::*;
 pub use evaluator::*;
 pub use galois_key::*;
+pub use key_derivation::*;
 pub use key_switching_key::*;
 pub use modulus::*;
 pub use nb_theory::*;
 pub use ntt::NttOperator;
-pub use parameters::{HybridKeySwitchingParameters, PolyType};
+pub use parameters::{HybridKeySwitchingParameters, KeySwitchingMethod, PolyType};
 pub use plaintext::*;
 pub use poly::{Poly, Representation, Substitution};
+pub use public_key::*;
+pub use re_encryption_key::*;
 pub use relinearization_key::*;
 pub use secret_key::*;
+pub use threshold::*;
 pub use utils::*;
 
 pub type BfvParameters = parameters::BfvParameters<NttOperator>;
@@ -1,6 +1,6 @@
 use crate::modulus::Modulus;
 use num_bigint_dig::{prime::probably_prime, BigUint};
-use rand::{thread_rng, Rng};
+use rand::{Rng, RngCore};
 
 pub fn generate_primes_vec(
     sizes: &[usize],
@@ -51,9 +51,8 @@ pub fn generate_prime(num_bits: usize, modulo: u64, upper_bound: u64) -> Option<
     }
 }
 
-// Finds 2n_th primitive root of unity in field mod p
-pub fn primitive_element(p: u64, n: usize) -> Option<u64> {
-    let mut rng = thread_rng();
+// Finds 2n_th primitive root of unity in field mod p. Candidates are sampled using `rng`.
+pub fn primitive_element<R: RngCore>(p: u64, n: usize, rng: &mut R) -> Option<u64> {
     let p = Modulus::new(p);
     let m = (n as u64) * 2;
 
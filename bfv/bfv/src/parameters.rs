@@ -18,6 +18,17 @@ pub enum PolyType {
     QP,
 }
 
+/// Key switching method used by relinearization and Galois keys
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum KeySwitchingMethod {
+    /// Hybrid key switching. Decomposes Q into `dnum` parts of `alpha` moduli each and requires
+    /// special moduli P.
+    Hybrid,
+    /// Brakerski-Vaikuntanathan key switching. Decomposes Q into individual moduli and does not
+    /// require special moduli, at the cost of larger keys and larger noise growth.
+    BV,
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct BfvParameters<T: Ntt> {
     pub ciphertext_moduli: Vec<u64>,
@@ -85,12 +96,15 @@ pub struct BfvParameters<T: Ntt> {
     pub ql_inv: Vec<Vec<f64>>,
     pub alphal_modpl: Vec<Array2<u64>>,
 
+    // Key switching
+    pub key_switching_method: KeySwitchingMethod,
+
     // Hybrid key switching
     pub special_moduli: Option<Vec<u64>>,
     pub special_moduli_ops: Option<Vec<Modulus>>,
     pub special_moduli_ntt_ops: Option<Vec<T>>,
     pub dnum: Option<usize>,
-    pub alpha: Option<usize>, // fixed to 3
+    pub alpha: Option<usize>,
 
     // Hybrid key switching key parameters
     pub hybrid_ksk_parameters: Option<Vec<HybridKeySwitchingParameters>>,
@@ -578,6 +592,9 @@ where
             ql_inv,
             alphal_modpl,
 
+            // Key switching //
+            key_switching_method: KeySwitchingMethod::Hybrid,
+
             // Hybrid key switching //
             special_moduli: None,
             alpha: None,
@@ -596,9 +613,27 @@ where
         self.hw = hw;
     }
 
-    pub fn enable_hybrid_key_switching(&mut self, specialp_bits: &[usize; 3]) {
-        const ALPHA: usize = 3;
-        let dnum = (self.ciphertext_moduli.len() as f64 / ALPHA as f64).ceil() as usize;
+    /// Enables hybrid key switching with `alpha` equal to number of special moduli, ie special
+    /// moduli P are about as large as every part of Q.
+    pub fn enable_hybrid_key_switching(&mut self, specialp_bits: &[usize]) {
+        self.enable_hybrid_key_switching_with_alpha(specialp_bits.len(), specialp_bits);
+    }
+
+    /// Enables hybrid key switching where Q is decomposed into `dnum = ceil(q_size / alpha)`
+    /// parts of `alpha` moduli each.
+    ///
+    /// Larger `alpha` means fewer parts, thus smaller keys and faster key switching, but requires
+    /// larger special moduli P (P must be at least as large as every part) to keep key switching
+    /// noise low. `alpha >= q_size` results in `dnum = 1`.
+    pub fn enable_hybrid_key_switching_with_alpha(
+        &mut self,
+        alpha: usize,
+        specialp_bits: &[usize],
+    ) {
+        assert!(alpha > 0);
+        assert!(!specialp_bits.is_empty());
+
+        let dnum = (self.ciphertext_moduli.len() as f64 / alpha as f64).ceil() as usize;
         let special_moduli =
             generate_primes_vec(specialp_bits, self.degree, &self.ciphertext_moduli);
         let special_moduli_ops = special_moduli
@@ -610,8 +645,9 @@ where
             .map(|pj| T::new(self.degree, *pj))
             .collect_vec();
 
+        self.key_switching_method = KeySwitchingMethod::Hybrid;
         self.special_moduli = Some(special_moduli);
-        self.alpha = Some(ALPHA);
+        self.alpha = Some(alpha);
         self.dnum = Some(dnum);
         self.special_moduli_ntt_ops = Some(special_moduli_ntt_ops);
         self.special_moduli_ops = Some(special_moduli_ops);
@@ -622,13 +658,25 @@ where
             .map(|level| {
                 let ksk_ctx = self.poly_ctx(&PolyType::Q, level);
                 let specialp_ctx = self.poly_ctx(&PolyType::SpecialP, level);
-                HybridKeySwitchingParameters::new(&ksk_ctx, &specialp_ctx, ALPHA)
+                HybridKeySwitchingParameters::new(&ksk_ctx, &specialp_ctx, alpha)
             })
             .collect_vec();
 
         self.hybrid_ksk_parameters = Some(params);
     }
 
+    /// Enables BV key switching, which decomposes Q into individual moduli. Does not require
+    /// special moduli.
+    pub fn enable_bv_key_switching(&mut self) {
+        self.key_switching_method = KeySwitchingMethod::BV;
+        self.special_moduli = None;
+        self.alpha = None;
+        self.dnum = None;
+        self.special_moduli_ntt_ops = None;
+        self.special_moduli_ops = None;
+        self.hybrid_ksk_parameters = None;
+    }
+
     pub fn poly_ctx(&self, poly_type: &PolyType, level: usize) -> PolyContext<'_, T> {
         let level_index = self.q_size - level;
         match poly_type {
@@ -672,7 +720,11 @@ where
                             .as_slice(),
                         &[],
                     ),
-                    moduli_count: self.alpha.expect("SpecialP missing"),
+                    moduli_count: self
+                        .special_moduli
+                        .as_ref()
+                        .expect("SpecialP missing")
+                        .len(),
                     degree: self.degree,
                 };
                 tmp
@@ -687,7 +739,8 @@ where
                         &self.ciphertext_ntt_ops[..level_index],
                         &self.special_moduli_ntt_ops.as_ref().expect("QP missing"),
                     ),
-                    moduli_count: level_index + self.alpha.expect("QP missing"),
+                    moduli_count: level_index
+                        + self.special_moduli.as_ref().expect("QP missing").len(),
                     degree: self.degree,
                 };
                 tmp
@@ -846,7 +899,11 @@ impl HybridKeySwitchingParameters {
                 p_hat_modq.push(((&p / modpi.modulus()) % modqj.modulus()).to_u64().unwrap());
             });
         });
-        let p_hat_modq = Array2::from_shape_vec((ksk_ctx.moduli_count, alpha), p_hat_modq).unwrap();
+        let p_hat_modq = Array2::from_shape_vec(
+            (ksk_ctx.moduli_count, specialp_ctx.moduli_count),
+            p_hat_modq,
+        )
+        .unwrap();
         let mut p_inv_modq = vec![];
         // Precompute for dividing values in basis Q by P (approx_mod_down)
         ksk_ctx.iter_moduli_ops().for_each(|modqi| {
@@ -143,6 +143,52 @@ impl Plaintext {
         m
     }
 
+    /// Encodes a given signed message `m` to plaintext using given `encoding`. Each value is mapped
+    /// to its centered representative in Z_t, ie negative value `v` is mapped to `t + v`.
+    ///
+    /// Panics if any value lies outside of `signed_range`, ie `[-(t-1)/2, (t-1)/2]`
+    pub fn encode_signed(m: &[i64], params: &BfvParameters, encoding: Encoding) -> Plaintext {
+        let t = params.plaintext_modulus;
+        let (lower, upper) = Plaintext::signed_range(params);
+        let m = m
+            .iter()
+            .map(|v| {
+                assert!(
+                    lower <= *v && *v <= upper,
+                    "Value {v} outside of signed range [{lower}, {upper}]"
+                );
+                if *v < 0 {
+                    t - v.unsigned_abs()
+                } else {
+                    *v as u64
+                }
+            })
+            .collect_vec();
+        Plaintext::encode(&m, params, encoding)
+    }
+
+    /// Decodes plaintext to signed message by mapping each value to its centered representative in Z_t,
+    /// ie values greater than `(t-1)/2` are mapped to `v - t`.
+    pub fn decode_signed(&self, encoding: Encoding, params: &BfvParameters) -> Vec<i64> {
+        let t = params.plaintext_modulus;
+        let m: Vec<u64> = self.decode(encoding, params);
+        m.iter()
+            .map(|v| {
+                if *v > t / 2 {
+                    -((t - *v) as i64)
+                } else {
+                    *v as i64
+                }
+            })
+            .collect_vec()
+    }
+
+    /// Returns inclusive range `(lower, upper)` of values supported by signed encoding
+    pub fn signed_range(params: &BfvParameters) -> (i64, i64) {
+        let bound = ((params.plaintext_modulus - 1) / 2) as i64;
+        (-bound, bound)
+    }
+
     /// Returns message polynomial `m` scaled by Q/t
     ///
     /// Panics if encoding is not specified
@@ -256,4 +302,39 @@ impl<'a> TryDecodingWithParameters<&'a Plaintext> for Vec<u32> {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::{Evaluator, SecretKey};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn signed_encoding_works() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(3, 1 << 4);
+        let (lower, upper) = Plaintext::signed_range(&params);
+
+        let mut m = (0..params.degree)
+            .map(|_| rng.gen_range(lower..=upper))
+            .collect_vec();
+        m[0] = lower;
+        m[1] = upper;
+        m[2] = -1;
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let evaluator = Evaluator::new(params);
+        let pt = evaluator.plaintext_encode_signed(&m, Encoding::default());
+        let ct = evaluator.encrypt(&sk, &pt, &mut rng);
+
+        let res =
+            evaluator.plaintext_decode_signed(&evaluator.decrypt(&sk, &ct), Encoding::default());
+        assert_eq!(res, m);
+    }
+
+    #[test]
+    #[should_panic]
+    fn signed_encoding_out_of_range() {
+        let params = BfvParameters::default(3, 1 << 4);
+        let (_, upper) = Plaintext::signed_range(&params);
+        Plaintext::encode_signed(&[upper + 1], &params, Encoding::default());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{convert_from_bytes, convert_to_bytes};
 use itertools::{izip, Itertools};
 use ndarray::Array2;
+use zeroize::Zeroize;
 pub mod poly_context;
 
 pub use poly_context::PolyContext;
@@ -12,7 +13,7 @@ pub enum Representation {
     Unknown,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Substitution {
     pub(crate) exponent: usize,
     pub(crate) power_bitrev: Box<[usize]>,
@@ -59,6 +60,14 @@ pub struct Poly {
     pub(crate) representation: Representation,
 }
 
+/// Polynomials are not wiped on drop by default, since most of them are public. Wrap secret
+/// dependent polynomials in `zeroize::Zeroizing` instead.
+impl Zeroize for Poly {
+    fn zeroize(&mut self) {
+        self.coefficients.iter_mut().for_each(|c| c.zeroize());
+    }
+}
+
 impl Poly {
     pub fn new(coefficients: Array2<u64>, representation: Representation) -> Poly {
         Poly {
@@ -7,12 +7,11 @@ use crate::{
 use crypto_bigint::U192;
 use itertools::{izip, Itertools};
 use ndarray::{azip, s, Array2, ArrayView2, Axis, IntoNdProducer};
-use num_bigint::BigUint;
+use num_bigint::{BigUint, RandBigInt};
 use num_traits::{identities::One, ToPrimitive, Zero};
 use rand::{CryptoRng, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use seq_macro::seq;
-use std::mem::{self, MaybeUninit};
 use traits::Ntt;
 
 #[derive(PartialEq)]
@@ -131,6 +130,26 @@ where
         self.try_convert_from_i64_small(&v, representation)
     }
 
+    /// Creates a polynomial with values `x - y` for `x, y` sampled uniformly from `[0, 2^bits)`.
+    /// Used as flooding (smudging) noise to hide noise of ciphertexts.
+    pub fn random_flooding<R: CryptoRng + RngCore>(
+        &self,
+        representation: Representation,
+        bits: usize,
+        rng: &mut R,
+    ) -> Poly {
+        let mut sample = || {
+            let values = (0..self.degree)
+                .map(|_| rng.gen_biguint(bits as u64))
+                .collect_vec();
+            self.try_convert_from_biguint(&values, Representation::Coefficient)
+        };
+        let mut poly = sample();
+        self.sub_assign(&mut poly, &sample());
+        self.change_representation(&mut poly, representation);
+        poly
+    }
+
     /// Changes representation of the polynomial to `to` representation
     pub fn change_representation(&self, poly: &mut Poly, to: Representation) {
         if poly.representation == Representation::Evaluation {
@@ -626,28 +645,28 @@ where
         p_moduli_ops: &[Modulus],
     ) -> Array2<u64> {
         debug_assert!(q_moduli_ops.len() == q_coefficients.shape()[0]);
-        debug_assert!(q_moduli_ops.len() <= 3);
+        // Sum of `q_size` products of two 64 bit values is accumulated in u128. This does not
+        // overflow as long as `q_size * q_max * p_max < 2^128`, which holds for up to 256 moduli
+        // of 60 bits.
+        debug_assert!(q_moduli_ops.len() <= 256);
 
         let mut p_coeffs = Array2::<u64>::uninit((p_moduli_ops.len(), degree));
 
         let p_size = p_moduli_ops.len();
         let q_size = q_coefficients.shape()[0];
+        let mut tmp = vec![0u64; q_size * 8];
         unsafe {
             for ri in (0..degree).step_by(8) {
-                let mut tmp: [MaybeUninit<u64>; 3 * 8] = MaybeUninit::uninit().assume_init();
-
                 for i in 0..q_size {
                     let modq = q_moduli_ops.get_unchecked(i);
                     let op = *q_hat_inv_modq.get_unchecked(i);
 
                     seq!(N in 0..8 {
-                        tmp.get_unchecked_mut(i*8+N)
-                        .write(modq.mul_mod_fast(*q_coefficients.uget((i, ri+N)), op));
+                        *tmp.get_unchecked_mut(i*8+N) =
+                            modq.mul_mod_fast(*q_coefficients.uget((i, ri+N)), op);
                     });
                 }
-                // tmp.set_len(q_size);
 
-                let tmp = mem::transmute::<_, [u64; 3 * 8]>(tmp);
                 for j in 0..p_size {
                     seq!(N in 0..8 {
                         let mut s~N = 0u128;
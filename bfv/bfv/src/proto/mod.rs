@@ -2,8 +2,9 @@ use std::collections::HashMap;
 
 use crate::{
     convert_bytes_to_ternary, convert_from_bytes, convert_ternary_to_bytes, convert_to_bytes,
-    BfvParameters, Ciphertext, EvaluationKey, GaloisKey, HybridKeySwitchingKey, Poly, PolyContext,
-    PolyType, RelinearizationKey, Representation, SecretKey, Substitution,
+    decryption_proof::has_shape, BVKeySwitchingKey, BfvParameters, Ciphertext, DecryptionProof,
+    EvaluationKey, GaloisKey, HybridKeySwitchingKey, KeySwitchingKey, Poly, PolyContext, PolyType,
+    RelinearizationKey, Representation, SecretKey, Substitution,
 };
 use itertools::{izip, Itertools};
 use ndarray::Array2;
@@ -244,21 +245,115 @@ impl<'a> TryFromWithPolyContext<'a> for HybridKeySwitchingKey {
     }
 }
 
+// BV Key Switching Key //
+impl<'a> TryFromWithPolyContext<'a> for proto::BVKeySwitchingKey {
+    type PolyContext = PolyContext<'a>;
+    type Value = BVKeySwitchingKey;
+    fn try_from_with_context(value: &Self::Value, poly_ctx: &'a Self::PolyContext) -> Self {
+        // c1s are always generated from seed, thus only c0s are serialized
+        let c0s = value
+            .c0s
+            .iter()
+            .map(|p| {
+                let mut p = p.clone();
+                poly_ctx.change_representation(&mut p, Representation::Coefficient);
+                proto::Poly::try_from_with_context(&p, &poly_ctx)
+            })
+            .collect_vec();
+
+        proto::BVKeySwitchingKey {
+            c0s,
+            seed: value.seed.to_vec(),
+        }
+    }
+}
+
+impl<'a> TryFromWithPolyContext<'a> for BVKeySwitchingKey {
+    type PolyContext = PolyContext<'a>;
+    type Value = proto::BVKeySwitchingKey;
+    fn try_from_with_context(value: &Self::Value, poly_ctx: &'a Self::PolyContext) -> Self {
+        let c0s = value
+            .c0s
+            .iter()
+            .map(|p| {
+                let mut p = Poly::try_from_with_context(p, &poly_ctx);
+                poly_ctx.change_representation(&mut p, Representation::Evaluation);
+                p
+            })
+            .collect_vec();
+
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        seed.copy_from_slice(&value.seed);
+        let c1s = BVKeySwitchingKey::generate_c1(c0s.len(), poly_ctx, seed);
+
+        BVKeySwitchingKey {
+            c0s: c0s.into_boxed_slice(),
+            c1s: c1s.into_boxed_slice(),
+            seed,
+        }
+    }
+}
+
+/// Returns `(ksk, bv_ksk)` fields of `proto::RelinearizationKey` and `proto::GaloisKey`
+fn key_switching_key_to_proto(
+    value: &KeySwitchingKey,
+    parameters: &BfvParameters,
+    level: usize,
+) -> (
+    Option<proto::HybridKeySwitchingKey>,
+    Option<proto::BVKeySwitchingKey>,
+) {
+    match value {
+        KeySwitchingKey::Hybrid(ksk) => {
+            let ctx = parameters.poly_ctx(&PolyType::QP, level);
+            (
+                Some(proto::HybridKeySwitchingKey::try_from_with_context(
+                    ksk, &ctx,
+                )),
+                None,
+            )
+        }
+        KeySwitchingKey::BV(ksk) => {
+            let ctx = parameters.poly_ctx(&PolyType::Q, level);
+            (
+                None,
+                Some(proto::BVKeySwitchingKey::try_from_with_context(ksk, &ctx)),
+            )
+        }
+    }
+}
+
+fn key_switching_key_from_proto(
+    ksk: Option<&proto::HybridKeySwitchingKey>,
+    bv_ksk: Option<&proto::BVKeySwitchingKey>,
+    parameters: &BfvParameters,
+    level: usize,
+) -> KeySwitchingKey {
+    match (ksk, bv_ksk) {
+        (Some(ksk), None) => {
+            let ctx = parameters.poly_ctx(&PolyType::QP, level);
+            KeySwitchingKey::Hybrid(HybridKeySwitchingKey::try_from_with_context(ksk, &ctx))
+        }
+        (None, Some(bv_ksk)) => {
+            let ctx = parameters.poly_ctx(&PolyType::Q, level);
+            KeySwitchingKey::BV(BVKeySwitchingKey::try_from_with_context(bv_ksk, &ctx))
+        }
+        _ => panic!("Expected exactly one key switching key"),
+    }
+}
+
 // Galois Key //
 impl TryFromWithParameters for proto::GaloisKey {
     type Parameters = BfvParameters;
     type Value = GaloisKey;
 
     fn try_from_with_parameters(value: &Self::Value, parameters: &Self::Parameters) -> Self {
-        let ctx = parameters.poly_ctx(&PolyType::QP, value.level);
-        let ksk = Some(proto::HybridKeySwitchingKey::try_from_with_context(
-            &value.ksk_key,
-            &ctx,
-        ));
+        let (ksk, bv_ksk) = key_switching_key_to_proto(&value.ksk_key, parameters, value.level);
         proto::GaloisKey {
             exponent: value.substitution.exponent as u32,
             ksk,
             level: value.level as u32,
+            bv_ksk,
         }
     }
 }
@@ -271,8 +366,12 @@ impl TryFromWithParameters for GaloisKey {
         let substitution = Substitution::new(value.exponent as usize, parameters.degree);
         let level = value.level as usize;
 
-        let ctx = parameters.poly_ctx(&PolyType::QP, level);
-        let ksk = HybridKeySwitchingKey::try_from_with_context(&value.ksk.as_ref().unwrap(), &ctx);
+        let ksk = key_switching_key_from_proto(
+            value.ksk.as_ref(),
+            value.bv_ksk.as_ref(),
+            parameters,
+            level,
+        );
         GaloisKey {
             substitution,
             ksk_key: ksk,
@@ -287,18 +386,16 @@ impl TryFromWithParameters for proto::RelinearizationKey {
     type Value = RelinearizationKey;
     fn try_from_with_parameters(value: &Self::Value, parameters: &Self::Parameters) -> Self {
         let level = value.level;
-        let ctx = parameters.poly_ctx(&PolyType::QP, level);
 
         // message types default to optional in proto3. For more info check this
         // answer https://github.com/tokio-rs/prost/discussions/679 and the one linked in it.
         // This is enforced by proto3, not something prost does.
-        let ksk = Some(proto::HybridKeySwitchingKey::try_from_with_context(
-            &value.ksk, &ctx,
-        ));
+        let (ksk, bv_ksk) = key_switching_key_to_proto(&value.ksk, parameters, level);
 
         proto::RelinearizationKey {
             ksk,
             level: level as u32,
+            bv_ksk,
         }
     }
 }
@@ -308,10 +405,11 @@ impl TryFromWithParameters for RelinearizationKey {
     type Value = proto::RelinearizationKey;
     fn try_from_with_parameters(value: &Self::Value, parameters: &Self::Parameters) -> Self {
         let level = value.level as usize;
-        let ctx = parameters.poly_ctx(&PolyType::QP, level);
-        let ksk = HybridKeySwitchingKey::try_from_with_context(
-            value.ksk.as_ref().expect("Rlk missing"),
-            &ctx,
+        let ksk = key_switching_key_from_proto(
+            value.ksk.as_ref(),
+            value.bv_ksk.as_ref(),
+            parameters,
+            level,
         );
 
         RelinearizationKey { ksk, level }
@@ -373,9 +471,99 @@ impl TryFromWithParameters for EvaluationKey {
     }
 }
 
+// Decryption Proof //
+impl TryFromWithParameters for proto::DecryptionProof {
+    type Parameters = BfvParameters;
+    type Value = DecryptionProof;
+
+    fn try_from_with_parameters(value: &Self::Value, parameters: &Self::Parameters) -> Self {
+        let ctx = parameters.poly_ctx(&PolyType::Q, value.level);
+        let [z_s, z_e, z_v] = [&value.z_s, &value.z_e, &value.z_v]
+            .map(|z| Some(proto::Poly::try_from_with_context(z, &ctx)));
+
+        proto::DecryptionProof {
+            challenge: value.challenge.to_vec(),
+            z_s,
+            z_e,
+            z_v,
+            key_noise_bits: value.key_noise_bits,
+            noise_bits: value.noise_bits,
+            level: value.level as u32,
+        }
+    }
+}
+
+impl TryFromWithParameters for DecryptionProof {
+    type Parameters = BfvParameters;
+    type Value = proto::DecryptionProof;
+
+    /// Proofs are received from untrusted provers, thus malformed `value` does not panic but
+    /// decodes to a proof that `DecryptionProof::verify` rejects
+    fn try_from_with_parameters(value: &Self::Value, parameters: &Self::Parameters) -> Self {
+        decryption_proof_from_proto(value, parameters).unwrap_or_else(DecryptionProof::malformed)
+    }
+}
+
+fn decryption_proof_from_proto(
+    value: &proto::DecryptionProof,
+    parameters: &BfvParameters,
+) -> Option<DecryptionProof> {
+    let level = value.level as usize;
+    if level > parameters.max_level || value.challenge.len() != 32 {
+        return None;
+    }
+    let ctx = parameters.poly_ctx(&PolyType::Q, level);
+
+    // bounds of the proof are `2^noise_bits`, thus reject bit sizes that can't be sound before
+    // they are ever used
+    let q_bits = ctx.big_q().bits();
+    if value.key_noise_bits > q_bits || value.noise_bits > q_bits {
+        return None;
+    }
+
+    let mut challenge = [0u8; 32];
+    challenge.copy_from_slice(&value.challenge);
+    Some(DecryptionProof {
+        challenge,
+        z_s: checked_poly_from_proto(value.z_s.as_ref()?, &ctx)?,
+        z_e: checked_poly_from_proto(value.z_e.as_ref()?, &ctx)?,
+        z_v: checked_poly_from_proto(value.z_v.as_ref()?, &ctx)?,
+        key_noise_bits: value.key_noise_bits,
+        noise_bits: value.noise_bits,
+        level,
+    })
+}
+
+/// Same as `Poly::try_from_with_context` except that it returns None, instead of panicking, if
+/// `poly` is not a polynomial of `poly_ctx` with coefficients reduced modulo each modulus
+fn checked_poly_from_proto(poly: &proto::Poly, poly_ctx: &PolyContext<'_>) -> Option<Poly> {
+    let degree = poly_ctx.degree();
+    let mut coefficients = Vec::with_capacity(poly.coefficients.len() * degree);
+    for (xi, modqi) in izip!(poly.coefficients.iter(), poly_ctx.iter_moduli_ops()) {
+        // `convert_to_bytes` outputs `bits * degree / 8 + 1` bytes
+        let bits = (64 - modqi.modulus().leading_zeros()) as usize;
+        if xi.len() != bits * degree / 8 + 1 {
+            return None;
+        }
+        let values = convert_from_bytes(xi, modqi.modulus());
+        if values.len() != degree || values.iter().any(|v| *v >= modqi.modulus()) {
+            return None;
+        }
+        coefficients.extend(values);
+    }
+
+    let coefficients =
+        Array2::from_shape_vec((coefficients.len() / degree, degree), coefficients).ok()?;
+    let poly = Poly {
+        coefficients,
+        representation: Representation::Coefficient,
+    };
+    has_shape(poly_ctx, &poly, false).then_some(poly)
+}
+
 mod tests {
     use super::*;
-    use crate::{Encoding, Evaluator, SecretKey};
+    use crate::{Encoding, Evaluator, PublicKey, SecretKey};
     use prost::Message;
     use rand::thread_rng;
 
@@ -412,6 +600,55 @@ mod tests {
         assert_eq!(ct0, ct_back);
     }
 
+    #[test]
+    fn serialize_and_deserialize_decryption_proof() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(4, 1 << 4);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let pk = PublicKey::new(&params, &sk, &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let m = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(evaluator.params().degree, &mut rng);
+        let pt = evaluator.plaintext_encode(&m, Encoding::default());
+        let ct = evaluator.encrypt_with_public_key(&pk, &pt, &mut rng);
+        let ct = evaluator.sanitize(&ct, &pk, &mut rng);
+        let pt = evaluator.decrypt(&sk, &ct);
+        let proof = DecryptionProof::new(evaluator.params(), &sk, &pk, &ct, &pt, &mut rng);
+
+        let proof_proto =
+            proto::DecryptionProof::try_from_with_parameters(&proof, evaluator.params());
+        let bytes = proof_proto.encode_to_vec();
+        let proof_back = DecryptionProof::try_from_with_parameters(
+            &proto::DecryptionProof::decode(bytes.as_slice()).unwrap(),
+            evaluator.params(),
+        );
+        assert_eq!(proof, proof_back);
+        assert!(proof_back.verify(evaluator.params(), &pk, &ct, &pt));
+
+        // polynomial with missing modulus
+        let mut proof_proto_malformed = proof_proto.clone();
+        proof_proto_malformed
+            .z_e
+            .as_mut()
+            .unwrap()
+            .coefficients
+            .pop();
+        let proof_back =
+            DecryptionProof::try_from_with_parameters(&proof_proto_malformed, evaluator.params());
+        assert!(!proof_back.verify(evaluator.params(), &pk, &ct, &pt));
+
+        // truncated coefficients and challenge
+        let mut proof_proto_malformed = proof_proto.clone();
+        proof_proto_malformed.z_v.as_mut().unwrap().coefficients[0].pop();
+        proof_proto_malformed.challenge.pop();
+        let proof_back =
+            DecryptionProof::try_from_with_parameters(&proof_proto_malformed, evaluator.params());
+        assert!(!proof_back.verify(evaluator.params(), &pk, &ct, &pt));
+    }
+
     #[test]
     fn serialize_and_deserialize_poly() {
         let params = BfvParameters::default(3, 1 << 15);
@@ -467,6 +704,26 @@ mod tests {
         assert_eq!(rlk, rlk_back);
     }
 
+    #[test]
+    fn serialize_and_deserialize_bv_keys() {
+        let mut params = BfvParameters::default(6, 1 << 4);
+        params.enable_bv_key_switching();
+
+        let mut rng = thread_rng();
+        let sk = SecretKey::random(params.degree, params.hw, &mut rng);
+
+        let rlk = RelinearizationKey::new(&params, &sk, 0, &mut rng);
+        let rlk_proto = proto::RelinearizationKey::try_from_with_parameters(&rlk, &params);
+        assert!(rlk_proto.ksk.is_none());
+        let rlk_back = RelinearizationKey::try_from_with_parameters(&rlk_proto, &params);
+        assert_eq!(rlk, rlk_back);
+
+        let rtg = GaloisKey::new(3, &params, 1, &sk, &mut rng);
+        let rtg_proto = proto::GaloisKey::try_from_with_parameters(&rtg, &params);
+        let rtg_back = GaloisKey::try_from_with_parameters(&rtg_proto, &params);
+        assert_eq!(rtg, rtg_back);
+    }
+
     #[test]
     fn serialize_and_deserialize_ek() {
         let mut rng = thread_rng();
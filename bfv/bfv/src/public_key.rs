@@ -0,0 +1,115 @@
+use crate::key_switching_key::drop_moduli;
+use crate::{BfvParameters, Ciphertext, Plaintext, Poly, PolyType, Representation, SecretKey};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Public key `(c0, c1) = (-a*s + e, a)` in Q basis at level 0 and `Evaluation` representation,
+/// where `a` is generated from `seed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKey {
+    pub(crate) c0: Poly,
+    pub(crate) c1: Poly,
+    pub(crate) seed: <ChaCha8Rng as SeedableRng>::Seed,
+}
+
+impl PublicKey {
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rng: &mut R,
+    ) -> PublicKey {
+        let mut seed = <ChaCha8Rng as SeedableRng>::Seed::default();
+        rng.fill_bytes(&mut seed);
+        PublicKey::new_with_seed(params, sk, seed, rng)
+    }
+
+    /// Same as `new` except that `a` is generated from `seed`. Public keys generated by several
+    /// parties from a common `seed` can be aggregated (see `PublicKey::aggregate`).
+    pub fn new_with_seed<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        seed: <ChaCha8Rng as SeedableRng>::Seed,
+        rng: &mut R,
+    ) -> PublicKey {
+        let ctx = params.poly_ctx(&PolyType::Q, 0);
+
+        let mut c1 = ctx.random_with_seed(seed);
+        ctx.change_representation(&mut c1, Representation::Evaluation);
+
+        // -a*s + e
+        let mut c0 = ctx.random_gaussian(Representation::Coefficient, params.variance, rng);
+        ctx.change_representation(&mut c0, Representation::Evaluation);
+        ctx.sub_assign(&mut c0, &ctx.mul(&c1, &sk.to_poly(&ctx)));
+
+        PublicKey { c0, c1, seed }
+    }
+
+    /// Encrypts `pt` as `(c0*u + e0 + delta*m, c1*u + e1)` for random ternary `u`
+    pub fn encrypt<R: CryptoRng + RngCore>(
+        &self,
+        params: &BfvParameters,
+        pt: &Plaintext,
+        rng: &mut R,
+    ) -> Ciphertext {
+        let encoding = pt.encoding.clone().expect("Plaintext encoding missing!");
+        let level = encoding.level;
+        let ctx = params.poly_ctx(&PolyType::Q, level);
+
+        // public key is usable at any level by dropping moduli
+        let pk0 = drop_moduli(&self.c0, &ctx);
+        let pk1 = drop_moduli(&self.c1, &ctx);
+
+        let u = SecretKey::random_with_params(params, rng).to_poly(&ctx);
+        let mut c = [pk0, pk1].map(|pk| {
+            let mut e = ctx.random_gaussian(Representation::Coefficient, params.variance, rng);
+            ctx.change_representation(&mut e, Representation::Evaluation);
+            ctx.add_assign(&mut e, &ctx.mul(&pk, &u));
+            e
+        });
+
+        ctx.add_assign(
+            &mut c[0],
+            &pt.scale_plaintext(params, Representation::Evaluation),
+        );
+        c.iter_mut().for_each(|p| {
+            ctx.change_representation(p, Representation::Coefficient);
+        });
+
+        Ciphertext {
+            c: Vec::from(c),
+            poly_type: PolyType::Q,
+            level,
+            seed: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, Evaluator, PolyCache};
+    use rand::thread_rng;
+
+    #[test]
+    fn public_key_encryption_works() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(4, 1 << 4);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let pk = PublicKey::new(&params, &sk, &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        for level in [0, 2] {
+            let m = evaluator
+                .params()
+                .plaintext_modulus_op
+                .random_vec(evaluator.params().degree, &mut rng);
+            let pt = evaluator.plaintext_encode(&m, Encoding::simd(level, PolyCache::None));
+            let ct = evaluator.encrypt_with_public_key(&pk, &pt, &mut rng);
+            assert_eq!(ct.level(), level);
+            assert_eq!(
+                evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct), Encoding::default()),
+                m
+            );
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::key_switching_key::drop_moduli;
+use crate::{
+    BfvParameters, Ciphertext, HybridKeySwitchingKey, KeySwitchingKey, KeySwitchingMethod, Poly,
+    PolyType, Representation, SecretKey,
+};
+use itertools::Itertools;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+/// Public key `(c0, c1) = (-a*s + e, a)` in QP basis at level 0 and `Evaluation` representation.
+///
+/// Recipient of re-encrypted ciphertexts publishes it so that the owner of the source secret key
+/// can generate `ReEncryptionKey` to the recipient's key without learning the recipient's secret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReEncryptionPublicKey {
+    pub(crate) c0: Poly,
+    pub(crate) c1: Poly,
+}
+
+impl ReEncryptionPublicKey {
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rng: &mut R,
+    ) -> ReEncryptionPublicKey {
+        assert!(params.key_switching_method == KeySwitchingMethod::Hybrid);
+
+        let qp_ctx = params.poly_ctx(&PolyType::QP, 0);
+
+        let mut c1 = qp_ctx.random(Representation::Coefficient, rng);
+        qp_ctx.change_representation(&mut c1, Representation::Evaluation);
+
+        // -a*s + e
+        let mut c0 = qp_ctx.random_gaussian(Representation::Coefficient, params.variance, rng);
+        qp_ctx.change_representation(&mut c0, Representation::Evaluation);
+        qp_ctx.sub_assign(&mut c0, &qp_ctx.mul(&c1, &sk.to_poly(&qp_ctx)));
+
+        ReEncryptionPublicKey { c0, c1 }
+    }
+}
+
+/// Key switching key from secret key `s` to secret key `s'` of the recipient. Switching a
+/// ciphertext with it re-encrypts the ciphertext from `s` to `s'`, after which only the owner of
+/// `s'` can decrypt.
+///
+/// Key is generated by owner of `s` using recipient's `ReEncryptionPublicKey`. Each part
+/// `(c0, c1) = (pk0*u + e0 + g*s, pk1*u + e1)`, for fresh ternary `u`, is an encryption of `g*s`
+/// under `s'` and thus satisfies `c0 + c1*s' = g*s + (small)`, as required of any key switching
+/// key (see `HybridKeySwitchingKey`). Only supports hybrid key switching.
+#[derive(Debug, PartialEq)]
+pub struct ReEncryptionKey {
+    ksk: KeySwitchingKey,
+    level: usize,
+}
+
+impl ReEncryptionKey {
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        to: &ReEncryptionPublicKey,
+        level: usize,
+        rng: &mut R,
+    ) -> ReEncryptionKey {
+        assert!(params.key_switching_method == KeySwitchingMethod::Hybrid);
+
+        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
+        let ksk_params = params.hybrid_key_switching_params_at_level(level);
+
+        let pk0 = drop_moduli(&to.c0, &qp_ctx);
+        let pk1 = drop_moduli(&to.c1, &qp_ctx);
+        let s = sk.to_poly(&qp_ctx);
+
+        let (c0s, c1s): (Vec<Poly>, Vec<Poly>) = ksk_params
+            .g
+            .iter()
+            .map(|g| {
+                let u = SecretKey::random_with_params(params, rng).to_poly(&qp_ctx);
+                let [mut c0, c1] = [&pk0, &pk1].map(|pk| {
+                    let mut e =
+                        qp_ctx.random_gaussian(Representation::Coefficient, params.variance, rng);
+                    qp_ctx.change_representation(&mut e, Representation::Evaluation);
+                    qp_ctx.add_assign(&mut e, &qp_ctx.mul(pk, &u));
+                    e
+                });
+
+                // g*s, where g vanishes over special moduli
+                let mut gs = Zeroizing::new(qp_ctx.try_convert_from_biguint(
+                    vec![g.clone(); qp_ctx.degree].as_slice(),
+                    Representation::Evaluation,
+                ));
+                qp_ctx.mul_assign(&mut gs, &s);
+                qp_ctx.add_assign(&mut c0, &gs);
+
+                (c0, c1)
+            })
+            .unzip();
+
+        ReEncryptionKey {
+            ksk: KeySwitchingKey::Hybrid(HybridKeySwitchingKey {
+                seed: None,
+                c0s: c0s.into_boxed_slice(),
+                c1s: c1s.into_boxed_slice(),
+            }),
+            level,
+        }
+    }
+
+    /// Re-encrypts `ct` to recipient's secret key. `ct` must have 2 polynomials in
+    /// `Coefficient` representation.
+    pub fn switch(&self, ct: &Ciphertext, params: &BfvParameters) -> Ciphertext {
+        assert!(ct.c.len() == 2);
+        assert!(ct.c[0].representation == Representation::Coefficient);
+        // key generated at a lower level (ie with more moduli) is usable at any higher level
+        assert!(ct.level >= self.level);
+
+        let level = ct.level;
+        let q_ctx = params.poly_ctx(&PolyType::Q, level);
+
+        // c0 + c1*s = c0 + cs0 + cs1*s'
+        let (mut cs0, mut cs1) = self.ksk.switch(params, &ct.c[1], level);
+        q_ctx.change_representation(&mut cs0, Representation::Coefficient);
+        q_ctx.change_representation(&mut cs1, Representation::Coefficient);
+        q_ctx.add_assign(&mut cs0, &ct.c[0]);
+
+        Ciphertext {
+            c: vec![cs0, cs1],
+            poly_type: PolyType::Q,
+            level,
+            seed: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, EvaluationKey, Evaluator};
+    use rand::thread_rng;
+
+    #[test]
+    fn re_encryption_works() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(4, 1 << 4);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let sk_to = SecretKey::random_with_params(&params, &mut rng);
+        let pk_to = ReEncryptionPublicKey::new(&params, &sk_to, &mut rng);
+        let rek = ReEncryptionKey::new(&params, &sk, &pk_to, 0, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let m = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(evaluator.params().degree, &mut rng);
+        let pt = evaluator.plaintext_encode(&m, Encoding::default());
+        let ct = evaluator.encrypt(&sk, &pt, &mut rng);
+        let mut ct_sq = evaluator.relinearize(&evaluator.mul(&ct, &ct), &ek);
+        evaluator.mod_down_level(&mut ct_sq, 2);
+        let modt = &evaluator.params().plaintext_modulus_op;
+        let m_sq = m.iter().map(|v| modt.mul_mod_fast(*v, *v)).collect_vec();
+
+        // key generated at level 0 is usable at higher levels
+        for (ct, expected) in [(&ct, &m), (&ct_sq, &m_sq)] {
+            let ct_to = evaluator.switch_key(ct, &rek);
+            assert_eq!(
+                &evaluator
+                    .plaintext_decode(&evaluator.decrypt(&sk_to, &ct_to), Encoding::default()),
+                expected
+            );
+            assert_ne!(
+                &evaluator.plaintext_decode(&evaluator.decrypt(&sk, &ct_to), Encoding::default()),
+                expected
+            );
+        }
+    }
+}
@@ -1,11 +1,10 @@
-use crate::{
-    BfvParameters, Ciphertext, HybridKeySwitchingKey, PolyType, Representation, SecretKey,
-};
+use crate::{BfvParameters, Ciphertext, KeySwitchingKey, PolyType, Representation, SecretKey};
 use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
 
 #[derive(PartialEq, Debug)]
 pub struct RelinearizationKey {
-    pub(crate) ksk: HybridKeySwitchingKey,
+    pub(crate) ksk: KeySwitchingKey,
     pub(crate) level: usize,
 }
 
@@ -17,24 +16,14 @@ impl RelinearizationKey {
         rng: &mut R,
     ) -> RelinearizationKey {
         let q_ctx = params.poly_ctx(&PolyType::Q, level);
-        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
 
-        let mut sk_poly =
-            q_ctx.try_convert_from_i64_small(&sk.coefficients, Representation::Coefficient);
-        q_ctx.change_representation(&mut sk_poly, Representation::Evaluation);
+        let sk_poly = sk.to_poly(&q_ctx);
 
         // sk^2
-        let sk_sq = q_ctx.mul(&sk_poly, &sk_poly);
+        let sk_sq = Zeroizing::new(q_ctx.mul(&sk_poly, &sk_poly));
 
         // Key switching key
-        let ksk = HybridKeySwitchingKey::new(
-            params.hybrid_key_switching_params_at_level(level),
-            &sk_sq,
-            sk,
-            &qp_ctx,
-            params.variance,
-            rng,
-        );
+        let ksk = KeySwitchingKey::new(params, &sk_sq, sk, level, rng);
 
         RelinearizationKey { ksk, level }
     }
@@ -42,20 +31,13 @@ impl RelinearizationKey {
     pub fn relinearize(&self, ct: &Ciphertext, params: &BfvParameters) -> Ciphertext {
         assert!(ct.c.len() == 3); // otherwise invalid relinerization
         assert!(ct.c[0].representation == Representation::Coefficient);
-        assert!(ct.level == self.level);
+        // key generated at a lower level (ie with more moduli) is usable at any higher level
+        assert!(ct.level >= self.level);
 
         let level = ct.level;
         let q_ctx = params.poly_ctx(&PolyType::Q, level);
-        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
-        let specialp_ctx = params.poly_ctx(&PolyType::SpecialP, level);
 
-        let (mut cs0, mut cs1) = self.ksk.switch(
-            params.hybrid_key_switching_params_at_level(self.level),
-            &ct.c[2],
-            &qp_ctx,
-            &q_ctx,
-            &specialp_ctx,
-        );
+        let (mut cs0, mut cs1) = self.ksk.switch(params, &ct.c[2], level);
         q_ctx.change_representation(&mut cs0, Representation::Coefficient);
         q_ctx.change_representation(&mut cs1, Representation::Coefficient);
 
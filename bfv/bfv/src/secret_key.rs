@@ -5,12 +5,27 @@ use itertools::Itertools;
 use rand::distributions::{Distribution, Uniform};
 use rand::{CryptoRng, Rng, RngCore, SeedableRng};
 use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Clone, PartialEq, Debug)]
+/// Secret key is wiped from memory on drop. `Debug` does not print coefficients.
+#[derive(Clone, PartialEq)]
 pub struct SecretKey {
     pub(crate) coefficients: Box<[i64]>,
 }
 
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.coefficients.zeroize();
+    }
+}
+
 impl SecretKey {
     /// Generates a random secret key with fixed hamming weight `hw`.
     ///
@@ -53,6 +68,10 @@ impl SecretKey {
             indices.truncate(indices.len() - 1);
         }
 
+        // remaining indices and random bits reveal support and signs of the secret key
+        indices.zeroize();
+        random_bytes.zeroize();
+
         SecretKey {
             coefficients: sk.into_boxed_slice(),
         }
@@ -72,20 +91,26 @@ impl SecretKey {
     /// Panics if coefficients length does not match with degree of given bfv parameter
     ///
     /// Panics if each value in coefficients does not belong to ternary distribution (ie {-1,0,1}).
-    pub fn new(coefficients: Vec<i64>, degree: usize) -> SecretKey {
+    pub fn new(mut coefficients: Vec<i64>, degree: usize) -> SecretKey {
         assert!(coefficients.len() == degree);
         coefficients.iter().for_each(|c| {
             assert!(-1 <= *c && 1 >= *c);
         });
 
-        SecretKey {
-            coefficients: coefficients.into_boxed_slice(),
-        }
+        // copy instead of `into_boxed_slice`, which might reallocate without wiping `coefficients`
+        let sk = SecretKey {
+            coefficients: coefficients.as_slice().into(),
+        };
+        coefficients.zeroize();
+        sk
     }
 
-    /// Returns secret key polynomial for polynomial context at given level in Evaluation form
-    fn to_poly(&self, ctx: &PolyContext<'_>) -> Poly {
-        let mut p = ctx.try_convert_from_i64_small(&self.coefficients, Representation::Coefficient);
+    /// Returns secret key polynomial for polynomial context at given level in Evaluation form.
+    /// Polynomial is wiped on drop.
+    pub(crate) fn to_poly(&self, ctx: &PolyContext<'_>) -> Zeroizing<Poly> {
+        let mut p = Zeroizing::new(
+            ctx.try_convert_from_i64_small(&self.coefficients, Representation::Coefficient),
+        );
         ctx.change_representation(&mut p, Representation::Evaluation);
         p
     }
@@ -120,18 +145,19 @@ impl SecretKey {
         // sk*a
         ctx.mul_assign(&mut sk_poly, &a_eval);
 
-        let mut e = ctx.random_gaussian(Representation::Coefficient, params.variance, rng);
+        let mut e =
+            Zeroizing::new(ctx.random_gaussian(Representation::Coefficient, params.variance, rng));
         ctx.change_representation(&mut e, Representation::Evaluation);
 
         // e + m
-        ctx.add_assign(&mut e, &m);
+        let mut c0 = ctx.add(&e, &m);
         // e + m - sk*s
-        ctx.sub_assign(&mut e, &sk_poly);
+        ctx.sub_assign(&mut c0, &sk_poly);
 
-        ctx.change_representation(&mut e, Representation::Coefficient);
+        ctx.change_representation(&mut c0, Representation::Coefficient);
 
         Ciphertext {
-            c: vec![e, a],
+            c: vec![c0, a],
             poly_type: PolyType::Q,
             level: encoding.level,
             seed: Some(seed),
@@ -145,16 +171,17 @@ impl SecretKey {
 
         let ctx = params.poly_ctx(&ct.poly_type, ct.level);
 
-        let mut m = ct.c[0].clone();
+        // m = c0 + c1*s + ... reveals noise of `ct`
+        let mut m = Zeroizing::new(ct.c[0].clone());
         ctx.change_representation(&mut m, Representation::Evaluation);
 
         let s = self.to_poly(&ctx);
         let mut s_carry = s.clone();
         for i in 1..ct.c.len() {
             if ct.c[i].representation == Representation::Evaluation {
-                ctx.add_assign(&mut m, &ctx.mul(&s_carry, &ct.c[i]));
+                ctx.add_assign(&mut m, &Zeroizing::new(ctx.mul(&s_carry, &ct.c[i])));
             } else {
-                let mut tmp = ct.c[i].clone();
+                let mut tmp = Zeroizing::new(ct.c[i].clone());
                 ctx.change_representation(&mut tmp, Representation::Evaluation);
                 ctx.mul_assign(&mut tmp, &s_carry);
                 ctx.add_assign(&mut m, &tmp);
@@ -163,21 +190,7 @@ impl SecretKey {
         }
 
         ctx.change_representation(&mut m, Representation::Coefficient);
-        let m = ctx.scale_and_round_decryption(
-            &m,
-            &params.plaintext_modulus_op,
-            params.max_bit_size_by2,
-            &params.t_ql_hat_inv_modql_divql_modt[ct.level],
-            &params.t_bql_hat_inv_modql_divql_modt[ct.level],
-            &params.t_ql_hat_inv_modql_divql_frac[ct.level],
-            &params.t_bql_hat_inv_modql_divql_frac[ct.level],
-        );
-        Plaintext {
-            m,
-            encoding: None,
-            mul_poly: None,
-            add_sub_poly: None,
-        }
+        decrypt_phase(&m, ct.level, params)
     }
 
     pub fn measure_noise(&self, ct: &Ciphertext, params: &BfvParameters) -> u64 {
@@ -189,15 +202,16 @@ impl SecretKey {
 
         let ctx = params.poly_ctx(&ct.poly_type, ct.level);
 
-        let mut m = ct.c[0].clone();
+        // m = c0 + c1*s + ... reveals noise of `ct`
+        let mut m = Zeroizing::new(ct.c[0].clone());
         ctx.change_representation(&mut m, Representation::Evaluation);
         let s = self.to_poly(&ctx);
         let mut s_carry = s.clone();
         for i in 1..ct.c.len() {
             if ct.c[i].representation == Representation::Evaluation {
-                ctx.add_assign(&mut m, &ctx.mul(&s_carry, &ct.c[i]));
+                ctx.add_assign(&mut m, &Zeroizing::new(ctx.mul(&s_carry, &ct.c[i])));
             } else {
-                let mut tmp = ct.c[i].clone();
+                let mut tmp = Zeroizing::new(ct.c[i].clone());
                 ctx.change_representation(&mut tmp, Representation::Evaluation);
                 ctx.mul_assign(&mut tmp, &s_carry);
                 ctx.add_assign(&mut m, &tmp);
@@ -216,6 +230,27 @@ impl SecretKey {
     }
 }
 
+/// Returns plaintext of decryption phase `m = c0 + c1*s + ...` in Q basis at `level` and
+/// `Coefficient` representation, ie scales `m` by `t/Q` and rounds.
+pub(crate) fn decrypt_phase(m: &Poly, level: usize, params: &BfvParameters) -> Plaintext {
+    let ctx = params.poly_ctx(&PolyType::Q, level);
+    let m = ctx.scale_and_round_decryption(
+        m,
+        &params.plaintext_modulus_op,
+        params.max_bit_size_by2,
+        &params.t_ql_hat_inv_modql_divql_modt[level],
+        &params.t_bql_hat_inv_modql_divql_modt[level],
+        &params.t_ql_hat_inv_modql_divql_frac[level],
+        &params.t_bql_hat_inv_modql_divql_frac[level],
+    );
+    Plaintext {
+        m,
+        encoding: None,
+        mul_poly: None,
+        add_sub_poly: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,4 +298,17 @@ mod tests {
 
         println!("ones: {ones}, nones: {nones}, zeros: {zeros}")
     }
+
+    #[test]
+    fn secret_material_is_not_exposed() {
+        let sk = SecretKey::new(vec![1, -1, 0, 1], 4);
+        assert_eq!(format!("{:?}", sk), "SecretKey { .. }");
+
+        let mut p = Poly {
+            coefficients: ndarray::Array2::from_elem((2, 4), 7u64),
+            representation: Representation::Coefficient,
+        };
+        p.zeroize();
+        assert!(p.coefficients.iter().all(|c| *c == 0));
+    }
 }
@@ -0,0 +1,668 @@
+//! Multi-party (threshold) key generation and decryption.
+//!
+//! Every party holds its own `SecretKey` `s_i` and the collective secret key is `s = \sum_i s_i`,
+//! which is never materialised. Collective public, relinearization and Galois keys are aggregated
+//! from shares of all parties, where every share is computed from the party's secret key and a
+//! common reference seed (`crs`) agreed upon by all parties. Ciphertexts under collective keys are
+//! decrypted by combining `DecryptionShare`s of all parties (N-out-of-N), or of any `threshold`
+//! parties if secret keys are additionally split with `ShamirShare` (t-out-of-N).
+//!
+//! Decryption shares are flooded with smudging noise, so that they do not leak secret key shares
+//! through noise of the ciphertext.
+
+use crate::evaluation_key::rtg_galois_element;
+use crate::evaluator::FLOODING_STATISTICAL_SECURITY;
+use crate::key_switching_key::drop_moduli;
+use crate::secret_key::decrypt_phase;
+use crate::{
+    BfvParameters, Ciphertext, GaloisKey, HybridKeySwitchingKey, KeySwitchingKey,
+    KeySwitchingMethod, Plaintext, Poly, PolyContext, PolyType, PublicKey, RelinearizationKey,
+    Representation, SecretKey,
+};
+use itertools::{izip, Itertools};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use zeroize::Zeroizing;
+
+type Seed = <ChaCha8Rng as SeedableRng>::Seed;
+
+/// Returns gaussian error polynomial in `Evaluation` representation
+fn error<R: CryptoRng + RngCore>(
+    ctx: &PolyContext<'_>,
+    params: &BfvParameters,
+    rng: &mut R,
+) -> Poly {
+    let mut e = ctx.random_gaussian(Representation::Coefficient, params.variance, rng);
+    ctx.change_representation(&mut e, Representation::Evaluation);
+    e
+}
+
+/// Returns sum of `polys`
+fn sum<'a>(ctx: &PolyContext<'_>, mut polys: impl Iterator<Item = &'a Poly>) -> Poly {
+    let mut sum = polys.next().expect("Nothing to aggregate").clone();
+    polys.for_each(|p| ctx.add_assign(&mut sum, p));
+    sum
+}
+
+/// Returns element-wise sums of `rows`, all of which must have same length
+fn sum_rows<'a>(ctx: &PolyContext<'_>, rows: impl Iterator<Item = &'a [Poly]>) -> Vec<Poly> {
+    let rows = rows.collect_vec();
+    assert!(rows.iter().all(|r| r.len() == rows[0].len()));
+    (0..rows[0].len())
+        .map(|j| sum(ctx, rows.iter().map(|r| &r[j])))
+        .collect()
+}
+
+/// Multiplies every row of `poly` by corresponding value in `scalars`
+fn scalar_mul_assign(ctx: &PolyContext<'_>, poly: &mut Poly, scalars: &[u64]) {
+    izip!(
+        poly.coefficients.outer_iter_mut(),
+        ctx.iter_moduli_ops(),
+        scalars.iter()
+    )
+    .for_each(|(mut row, qi, s)| {
+        qi.scalar_mul_mod_fast_vec(row.as_slice_mut().unwrap(), *s);
+    });
+}
+
+/// Share of collective public key `(\sum_i -a*s_i + e_i, a)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKeyShare {
+    pub(crate) c0: Poly,
+}
+
+impl PublicKeyShare {
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        crs: Seed,
+        rng: &mut R,
+    ) -> PublicKeyShare {
+        PublicKeyShare {
+            c0: PublicKey::new_with_seed(params, sk, crs, rng).c0,
+        }
+    }
+}
+
+impl PublicKey {
+    /// Aggregates shares of all parties, generated with `crs`, into collective public key
+    pub fn aggregate(params: &BfvParameters, crs: Seed, shares: &[PublicKeyShare]) -> PublicKey {
+        let ctx = params.poly_ctx(&PolyType::Q, 0);
+
+        let mut c1 = ctx.random_with_seed(crs);
+        ctx.change_representation(&mut c1, Representation::Evaluation);
+
+        PublicKey {
+            c0: sum(&ctx, shares.iter().map(|s| &s.c0)),
+            c1,
+            seed: crs,
+        }
+    }
+}
+
+/// Share of collective Galois key. Galois key is `c0_j = g_j*\sigma(s) + e - a_j*s` for gadget
+/// `g_j` and substitution `\sigma`, which is linear in `s`, thus shares are aggregated by summing
+/// c0s of `GaloisKey`s of every party generated with common c1s.
+#[derive(Debug, PartialEq)]
+pub struct GaloisKeyShare {
+    pub(crate) key: GaloisKey,
+}
+
+impl GaloisKeyShare {
+    /// Generates share of Galois key for rotation by `rot_by` (`2 * degree - 1` for row swap) at
+    /// `level`
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rot_by: isize,
+        level: usize,
+        crs: Seed,
+        rng: &mut R,
+    ) -> GaloisKeyShare {
+        let exponent = rtg_galois_element(rot_by, params.degree);
+        GaloisKeyShare {
+            key: GaloisKey::new_with_seed(exponent, params, level, sk, crs, rng),
+        }
+    }
+}
+
+impl GaloisKey {
+    /// Aggregates shares of all parties, generated with common `crs`, into collective Galois key
+    pub fn aggregate(params: &BfvParameters, shares: Vec<GaloisKeyShare>) -> GaloisKey {
+        let mut keys = shares.into_iter().map(|s| s.key).collect_vec();
+        let first = keys.first().expect("Nothing to aggregate");
+        let (substitution, level) = (first.substitution.clone(), first.level);
+        assert!(keys
+            .iter()
+            .all(|k| k.substitution == substitution && k.level == level));
+
+        let ksks = keys.drain(..).map(|k| k.ksk_key).collect_vec();
+        GaloisKey {
+            substitution,
+            ksk_key: KeySwitchingKey::aggregate(ksks, params, level),
+            level,
+        }
+    }
+}
+
+/// Share of the first round of relinearization key generation.
+///
+/// Relinearization key must switch `s^2`, which is not linear in shares of `s`. Thus it is
+/// generated in two rounds, where in the first round every party with ephemeral secret `u_i`
+/// outputs `h0_ij = g_j*s_i + e - a_j*u_i` and `h1_ij = a_j*s_i + e` for common `a_j`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelinearizationKeyRound1Share {
+    pub(crate) h0s: Vec<Poly>,
+    pub(crate) h1s: Vec<Poly>,
+}
+
+/// Aggregated first round of relinearization key generation: `h0_j = g_j*s - a_j*u + e` and
+/// `h1_j = a_j*s + e`, where `u = \sum_i u_i`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelinearizationKeyRound1 {
+    pub(crate) h0s: Vec<Poly>,
+    pub(crate) h1s: Vec<Poly>,
+    pub(crate) level: usize,
+}
+
+/// Share of the second round of relinearization key generation: `s_i*h0_j + e` and
+/// `(u_i - s_i)*h1_j + e`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelinearizationKeyRound2Share {
+    pub(crate) h0s: Vec<Poly>,
+    pub(crate) h1s: Vec<Poly>,
+}
+
+impl RelinearizationKeyRound1Share {
+    /// Generates party's share of the first round at `level`. Returns the share and party's
+    /// ephemeral secret `u_i`, which party must keep for the second round.
+    ///
+    /// Only hybrid key switching is supported.
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        level: usize,
+        crs: Seed,
+        rng: &mut R,
+    ) -> (RelinearizationKeyRound1Share, SecretKey) {
+        assert!(params.key_switching_method == KeySwitchingMethod::Hybrid);
+
+        let u = SecretKey::random_with_params(params, rng);
+
+        // h0_ij = g_j*s_i + e - a_j*u_i, ie key switching key from s_i to u_i
+        let q_ctx = params.poly_ctx(&PolyType::Q, level);
+        let ksk = KeySwitchingKey::new_with_seed(params, &sk.to_poly(&q_ctx), &u, level, crs, rng);
+        let (h0s, a) = match ksk {
+            KeySwitchingKey::Hybrid(HybridKeySwitchingKey { c0s, c1s, .. }) => (c0s, c1s),
+            KeySwitchingKey::BV(_) => unreachable!(),
+        };
+
+        // h1_ij = a_j*s_i + e
+        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
+        let s = sk.to_poly(&qp_ctx);
+        let h1s = a
+            .iter()
+            .map(|a| {
+                let mut h1 = error(&qp_ctx, params, rng);
+                qp_ctx.add_assign(&mut h1, &qp_ctx.mul(a, &s));
+                h1
+            })
+            .collect_vec();
+
+        (
+            RelinearizationKeyRound1Share {
+                h0s: h0s.into_vec(),
+                h1s,
+            },
+            u,
+        )
+    }
+
+    /// Aggregates first round shares of all parties, generated with common `crs`
+    pub fn aggregate(
+        params: &BfvParameters,
+        level: usize,
+        shares: &[RelinearizationKeyRound1Share],
+    ) -> RelinearizationKeyRound1 {
+        let qp_ctx = params.poly_ctx(&PolyType::QP, level);
+        RelinearizationKeyRound1 {
+            h0s: sum_rows(&qp_ctx, shares.iter().map(|s| s.h0s.as_slice())),
+            h1s: sum_rows(&qp_ctx, shares.iter().map(|s| s.h1s.as_slice())),
+            level,
+        }
+    }
+}
+
+impl RelinearizationKeyRound2Share {
+    /// Generates party's share of the second round given aggregated first round and party's
+    /// ephemeral secret `u` returned by `RelinearizationKeyRound1Share::new`
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        u: &SecretKey,
+        round1: &RelinearizationKeyRound1,
+        rng: &mut R,
+    ) -> RelinearizationKeyRound2Share {
+        let qp_ctx = params.poly_ctx(&PolyType::QP, round1.level);
+        let s = sk.to_poly(&qp_ctx);
+        let u_minus_s = Zeroizing::new(qp_ctx.sub(&u.to_poly(&qp_ctx), &s));
+
+        let mut share = |polys: &[Poly], secret: &Poly| {
+            polys
+                .iter()
+                .map(|p| {
+                    let mut r = error(&qp_ctx, params, rng);
+                    qp_ctx.add_assign(&mut r, &qp_ctx.mul(p, secret));
+                    r
+                })
+                .collect_vec()
+        };
+        let h0s = share(round1.h0s.as_slice(), &*s);
+        let h1s = share(round1.h1s.as_slice(), &*u_minus_s);
+
+        RelinearizationKeyRound2Share { h0s, h1s }
+    }
+}
+
+impl RelinearizationKey {
+    /// Aggregates second round shares of all parties into collective relinearization key
+    /// `(c0_j, c1_j) = (\sum_i s_i*h0_j + (u_i - s_i)*h1_j, h1_j)`.
+    ///
+    /// Since `c0_j + c1_j*s = s*h0_j + u*h1_j = g_j*s^2 + s*e + u*e'`, key switches `s^2` to `s`.
+    pub fn aggregate(
+        params: &BfvParameters,
+        round1: &RelinearizationKeyRound1,
+        shares: &[RelinearizationKeyRound2Share],
+    ) -> RelinearizationKey {
+        let qp_ctx = params.poly_ctx(&PolyType::QP, round1.level);
+        let c0s = sum_rows(
+            &qp_ctx,
+            shares
+                .iter()
+                .flat_map(|s| [s.h0s.as_slice(), s.h1s.as_slice()]),
+        );
+
+        RelinearizationKey {
+            ksk: KeySwitchingKey::Hybrid(HybridKeySwitchingKey {
+                seed: None,
+                c0s: c0s.into_boxed_slice(),
+                c1s: round1.h1s.clone().into_boxed_slice(),
+            }),
+            level: round1.level,
+        }
+    }
+}
+
+/// Share of secret key of a party for t-out-of-N decryption. Party splits its secret key `s_i`
+/// with Shamir secret sharing into shares `f_i(1), ..., f_i(N)` of a random polynomial `f_i` of
+/// degree `threshold - 1` with `f_i(0) = s_i`, and sends `j^th` share to `j^th` party.
+pub struct ShamirShare {
+    pub(crate) poly: Zeroizing<Poly>,
+}
+
+impl ShamirShare {
+    /// Splits `sk` into shares for `parties` parties, any `threshold` of which can decrypt.
+    /// Shares are in Q basis at level 0.
+    pub fn split<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        threshold: usize,
+        parties: usize,
+        rng: &mut R,
+    ) -> Vec<ShamirShare> {
+        assert!(threshold >= 1 && threshold <= parties);
+        let ctx = params.poly_ctx(&PolyType::Q, 0);
+
+        // f(x) = s + r_1*x + ... + r_{t-1}*x^{t-1}
+        let s = sk.to_poly(&ctx);
+        let coefficients = (1..threshold)
+            .map(|_| Zeroizing::new(ctx.random(Representation::Evaluation, rng)))
+            .collect_vec();
+
+        (1..=parties as u64)
+            .map(|x| {
+                // Horner's rule
+                let mut y = Zeroizing::new(ctx.zero(Representation::Evaluation));
+                coefficients.iter().rev().for_each(|r| {
+                    ctx.add_assign(&mut y, r);
+                    scalar_mul_assign(&ctx, &mut y, &vec![x; ctx.moduli_count]);
+                });
+                ctx.add_assign(&mut y, &s);
+                ShamirShare { poly: y }
+            })
+            .collect()
+    }
+}
+
+/// Party's share `f(j) = \sum_i f_i(j)` of collective secret key for t-out-of-N decryption, where
+/// `j = index + 1`.
+pub struct ThresholdSecretKey {
+    pub(crate) index: usize,
+    pub(crate) poly: Zeroizing<Poly>,
+}
+
+impl ThresholdSecretKey {
+    /// Sums shares received by party `index` from all parties (including its own)
+    pub fn aggregate(
+        params: &BfvParameters,
+        index: usize,
+        shares: &[ShamirShare],
+    ) -> ThresholdSecretKey {
+        let ctx = params.poly_ctx(&PolyType::Q, 0);
+        ThresholdSecretKey {
+            index,
+            poly: Zeroizing::new(sum(&ctx, shares.iter().map(|s| &*s.poly))),
+        }
+    }
+
+    /// Returns Lagrange coefficient of party at `index` for interpolating at 0 from parties
+    /// `participants`, ie `\prod_{k != j} x_k / (x_k - x_j)` modulo every modulus of `ctx`
+    fn lagrange_coefficient(
+        index: usize,
+        participants: &[usize],
+        ctx: &PolyContext<'_>,
+    ) -> Vec<u64> {
+        assert!(participants.contains(&index));
+        assert!(participants.iter().all_unique());
+
+        let x_j = (index + 1) as u64;
+        ctx.iter_moduli_ops()
+            .map(|qi| {
+                let (num, den) =
+                    participants
+                        .iter()
+                        .filter(|k| **k != index)
+                        .fold((1, 1), |(num, den), k| {
+                            let x_k = (*k + 1) as u64;
+                            (
+                                qi.mul_mod_fast(num, x_k),
+                                qi.mul_mod_fast(den, qi.sub_mod_fast(x_k, x_j)),
+                            )
+                        });
+                qi.mul_mod_fast(num, qi.inv(den))
+            })
+            .collect()
+    }
+}
+
+/// Partial decryption `c1*s_i + e_smudge` of a party
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecryptionShare {
+    pub(crate) poly: Poly,
+}
+
+impl DecryptionShare {
+    /// Returns bits of smudging noise added to every decryption share of ciphertexts at `level`
+    /// combined from shares of `parties` parties: the largest noise whose sum over all parties
+    /// stays 2 bits below `Q/2t`, thus leaving `Q/4t` for noise of the ciphertext.
+    ///
+    /// Smudging noise hides noise of ciphertexts of up to `smudging_bits(..) - 40` bits with 40
+    /// bits of statistical security.
+    pub fn smudging_bits(params: &BfvParameters, level: usize, parties: usize) -> usize {
+        assert!(parties > 0);
+        let ctx = params.poly_ctx(&PolyType::Q, level);
+        let budget = (ctx.big_q() / (2 * params.plaintext_modulus)).bits() as usize;
+        let parties_bits = parties.next_power_of_two().ilog2() as usize;
+        budget.saturating_sub(parties_bits + 2)
+    }
+
+    /// Returns decryption share of party with secret key `sk` for N-out-of-N decryption by
+    /// `parties` parties.
+    ///
+    /// `ct` must have 2 polynomials (ie be relinearized). Smudging noise is sampled with
+    /// `smudging_bits(params, ct.level, parties)` bits.
+    pub fn new<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        sk: &SecretKey,
+        ct: &Ciphertext,
+        parties: usize,
+        rng: &mut R,
+    ) -> DecryptionShare {
+        let ctx = params.poly_ctx(&PolyType::Q, ct.level);
+        Self::with_secret(params, &sk.to_poly(&ctx), ct, parties, rng)
+    }
+
+    /// Returns decryption share of party with threshold secret key `tsk` for t-out-of-N
+    /// decryption by `participants`, which are indices of at least `threshold` parties.
+    pub fn new_threshold<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        tsk: &ThresholdSecretKey,
+        participants: &[usize],
+        ct: &Ciphertext,
+        rng: &mut R,
+    ) -> DecryptionShare {
+        let ctx = params.poly_ctx(&PolyType::Q, ct.level);
+
+        // lambda_j * f(j)
+        let mut s = Zeroizing::new(drop_moduli(&tsk.poly, &ctx).into_owned());
+        let lambda = ThresholdSecretKey::lagrange_coefficient(tsk.index, participants, &ctx);
+        scalar_mul_assign(&ctx, &mut s, &lambda);
+
+        Self::with_secret(params, &s, ct, participants.len(), rng)
+    }
+
+    /// Returns `c1*s + e_smudge` in `Coefficient` representation for `s` in Q basis at level of
+    /// `ct` and `Evaluation` representation
+    fn with_secret<R: CryptoRng + RngCore>(
+        params: &BfvParameters,
+        s: &Poly,
+        ct: &Ciphertext,
+        parties: usize,
+        rng: &mut R,
+    ) -> DecryptionShare {
+        assert!(ct.c.len() == 2);
+        assert!(ct.poly_type == PolyType::Q);
+        let ctx = params.poly_ctx(&PolyType::Q, ct.level);
+
+        let smudging_bits = Self::smudging_bits(params, ct.level, parties);
+        assert!(
+            smudging_bits > FLOODING_STATISTICAL_SECURITY,
+            "Insufficient noise budget for smudging of {parties} parties at level {}",
+            ct.level
+        );
+
+        let mut d = ct.c[1].clone();
+        ctx.change_representation(&mut d, Representation::Evaluation);
+        ctx.mul_assign(&mut d, s);
+        ctx.change_representation(&mut d, Representation::Coefficient);
+
+        ctx.add_assign(
+            &mut d,
+            &ctx.random_flooding(Representation::Coefficient, smudging_bits, rng),
+        );
+
+        DecryptionShare { poly: d }
+    }
+
+    /// Decrypts `ct` given decryption shares of all parties (N-out-of-N) or of all participants
+    /// (t-out-of-N)
+    pub fn combine(
+        params: &BfvParameters,
+        ct: &Ciphertext,
+        shares: &[DecryptionShare],
+    ) -> Plaintext {
+        assert!(ct.c.len() == 2);
+        let ctx = params.poly_ctx(&PolyType::Q, ct.level);
+
+        // c0 + \sum_i c1*s_i + e_i
+        let mut m = ct.c[0].clone();
+        ctx.change_representation(&mut m, Representation::Coefficient);
+        shares.iter().for_each(|s| ctx.add_assign(&mut m, &s.poly));
+
+        decrypt_phase(&m, ct.level, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encoding, EvaluationKey, Evaluator};
+    use rand::thread_rng;
+
+    fn random_seed<R: RngCore>(rng: &mut R) -> Seed {
+        let mut seed = Seed::default();
+        rng.fill_bytes(&mut seed);
+        seed
+    }
+
+    #[test]
+    fn threshold_works() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(4, 1 << 4);
+        let parties = 3;
+        let sks = (0..parties)
+            .map(|_| SecretKey::random_with_params(&params, &mut rng))
+            .collect_vec();
+
+        // collective public key
+        let crs = random_seed(&mut rng);
+        let pk_shares = sks
+            .iter()
+            .map(|sk| PublicKeyShare::new(&params, sk, crs, &mut rng))
+            .collect_vec();
+        let pk = PublicKey::aggregate(&params, crs, &pk_shares);
+
+        // collective relinearization key
+        let crs = random_seed(&mut rng);
+        let (round1_shares, us): (Vec<_>, Vec<_>) = sks
+            .iter()
+            .map(|sk| RelinearizationKeyRound1Share::new(&params, sk, 0, crs, &mut rng))
+            .unzip();
+        let round1 = RelinearizationKeyRound1Share::aggregate(&params, 0, &round1_shares);
+        let round2_shares = izip!(sks.iter(), us.iter())
+            .map(|(sk, u)| RelinearizationKeyRound2Share::new(&params, sk, u, &round1, &mut rng))
+            .collect_vec();
+        let rlk = RelinearizationKey::aggregate(&params, &round1, &round2_shares);
+
+        // collective Galois key
+        let crs = random_seed(&mut rng);
+        let rtg_shares = sks
+            .iter()
+            .map(|sk| GaloisKeyShare::new(&params, sk, 1, 0, crs, &mut rng))
+            .collect_vec();
+        let rtg = GaloisKey::aggregate(&params, rtg_shares);
+
+        let ek = EvaluationKey::from_keys(vec![rlk], vec![(1, rtg)]);
+        let evaluator = Evaluator::new(params);
+        let params = evaluator.params();
+
+        let modt = &params.plaintext_modulus_op;
+        let m0 = modt.random_vec(params.degree, &mut rng);
+        let m1 = modt.random_vec(params.degree, &mut rng);
+        let ct0 = evaluator.encrypt_with_public_key(
+            &pk,
+            &evaluator.plaintext_encode(&m0, Encoding::default()),
+            &mut rng,
+        );
+        let ct1 = evaluator.encrypt_with_public_key(
+            &pk,
+            &evaluator.plaintext_encode(&m1, Encoding::default()),
+            &mut rng,
+        );
+
+        let ct = evaluator.relinearize(&evaluator.mul(&ct0, &ct1), &ek);
+        let ct = evaluator.rotate(&ct, 1, &ek);
+
+        let row_size = params.degree / 2;
+        let expected = (0..params.degree)
+            .map(|i| {
+                let j = (i / row_size) * row_size + (i % row_size + 1) % row_size;
+                modt.mul_mod_fast(m0[j], m1[j])
+            })
+            .collect_vec();
+
+        // smudging noise of all parties fits in the noise budget
+        let smudging_bits = DecryptionShare::smudging_bits(params, ct.level, parties);
+        assert!(smudging_bits > FLOODING_STATISTICAL_SECURITY);
+        assert!(DecryptionShare::smudging_bits(params, ct.level, 2) > smudging_bits);
+
+        // N-out-of-N
+        let shares = sks
+            .iter()
+            .map(|sk| DecryptionShare::new(params, sk, &ct, parties, &mut rng))
+            .collect_vec();
+        let pt = DecryptionShare::combine(params, &ct, &shares);
+        assert_eq!(
+            evaluator.plaintext_decode(&pt, Encoding::default()),
+            expected
+        );
+        // all parties are required
+        let pt = DecryptionShare::combine(params, &ct, &shares[1..]);
+        assert_ne!(
+            evaluator.plaintext_decode(&pt, Encoding::default()),
+            expected
+        );
+
+        // 2-out-of-3
+        let threshold = 2;
+        let splits = sks
+            .iter()
+            .map(|sk| ShamirShare::split(params, sk, threshold, parties, &mut rng))
+            .collect_vec();
+        let tsks = (0..parties)
+            .map(|j| {
+                let received = splits
+                    .iter()
+                    .map(|s| ShamirShare {
+                        poly: s[j].poly.clone(),
+                    })
+                    .collect_vec();
+                ThresholdSecretKey::aggregate(params, j, &received)
+            })
+            .collect_vec();
+        for participants in [[0, 1], [0, 2], [1, 2]] {
+            let shares = participants
+                .iter()
+                .map(|j| {
+                    DecryptionShare::new_threshold(params, &tsks[*j], &participants, &ct, &mut rng)
+                })
+                .collect_vec();
+            let pt = DecryptionShare::combine(params, &ct, &shares);
+            assert_eq!(
+                evaluator.plaintext_decode(&pt, Encoding::default()),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn key_shares_use_distinct_a() {
+        let mut rng = thread_rng();
+        // 5 moduli with alpha = 3, ie 2 parts
+        let params = BfvParameters::default(5, 1 << 4);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let crs = random_seed(&mut rng);
+
+        let qp_ctx = params.poly_ctx(&PolyType::QP, 0);
+        let dnum = params.hybrid_key_switching_params_at_level(0).dnum;
+        assert!(dnum >= 2);
+        let a = HybridKeySwitchingKey::generate_c1(dnum, &qp_ctx, crs)
+            .into_iter()
+            .map(|mut a| {
+                qp_ctx.change_representation(&mut a, Representation::Evaluation);
+                a
+            })
+            .collect_vec();
+        a.iter().tuple_combinations().for_each(|(a0, a1)| {
+            assert_ne!(a0, a1);
+        });
+
+        // h1_j = a_j*s + e, ie every part uses its own a_j
+        let (share, _) = RelinearizationKeyRound1Share::new(&params, &sk, 0, crs, &mut rng);
+        let s = sk.to_poly(&qp_ctx);
+        let big_q = qp_ctx.big_q();
+        izip!(share.h1s.iter(), a.iter()).for_each(|(h1, a)| {
+            let mut e = qp_ctx.sub(h1, &qp_ctx.mul(a, &s));
+            qp_ctx.change_representation(&mut e, Representation::Coefficient);
+            qp_ctx.try_convert_to_biguint(&e).iter().for_each(|v| {
+                assert!(std::cmp::min(v.bits(), (&big_q - v).bits()) < 16);
+            });
+        });
+
+        let share = GaloisKeyShare::new(&params, &sk, 1, 0, crs, &mut rng);
+        match &share.key.ksk_key {
+            KeySwitchingKey::Hybrid(ksk) => assert_eq!(ksk.c1s.to_vec(), a),
+            KeySwitchingKey::BV(_) => unreachable!(),
+        }
+    }
+}
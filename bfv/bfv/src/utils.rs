@@ -29,6 +29,27 @@ pub fn rot_to_galois_element(i: isize, n: usize) -> usize {
     }
 }
 
+/// Returns non-adjacent form of `value`, ie signed powers of two `±2^i` that sum to `value` and
+/// no two of which are adjacent powers.
+///
+/// NAF has the least number of non-zero digits among all signed binary representations, thus
+/// composing a rotation from rotations by `±2^i` along NAF requires the fewest rotations.
+pub fn naf(mut value: isize) -> Vec<isize> {
+    let mut digits = vec![];
+    let mut power = 1;
+    while value != 0 {
+        if value & 1 == 1 {
+            // 1 if value = 1 mod 4, -1 if value = 3 mod 4
+            let digit = 2 - value.rem_euclid(4);
+            digits.push(digit * power);
+            value -= digit;
+        }
+        value >>= 1;
+        power <<= 1;
+    }
+    digits
+}
+
 pub fn mod_inverse_biguint_u64(a: &BigUint, m: u64) -> BigUint {
     let a_dig = BigUintDig::from_bytes_le(&a.to_bytes_le());
     let m_dig = BigUintDig::from_u64(m).unwrap();
@@ -256,6 +277,22 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn naf_works() {
+        for value in -1000isize..1000 {
+            let digits = naf(value);
+            assert_eq!(digits.iter().sum::<isize>(), value);
+            // powers of two in ascending order, none adjacent
+            digits.windows(2).for_each(|w| {
+                assert!(w[0].abs() * 4 <= w[1].abs());
+            });
+            digits
+                .iter()
+                .for_each(|d| assert!(d.abs().is_power_of_two()));
+        }
+        assert_eq!(naf(7), vec![-1, 8]);
+    }
+
     #[test]
     fn galois_el_works() {
         let mut v = vec![];
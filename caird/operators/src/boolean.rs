@@ -0,0 +1,273 @@
+//! Boolean gates and oblivious selection over ciphertexts that encrypt 0/1 in every slot.
+//!
+//! Gates with two ciphertext operands cost a single multiplication followed by relinearization,
+//! thus every output is a 2 polynomial ciphertext in `Coefficient` representation and can be fed
+//! to another gate. Gates with a plaintext operand only cost a plaintext multiplication and don't
+//! require relinearization.
+//!
+//! Multiplications are fused with `sum_of_products`: products are computed with `mul_lazy` and
+//! accumulated in PQ basis, thus a sum of any number of products costs a single
+//! `scale_and_round` and a single relinearization (see `select_one_hot`).
+
+use crate::utils::{mul_plain, one_minus};
+use bfv::{Ciphertext, Encoding, EvaluationKey, Evaluator, PolyCache};
+
+/// Returns `\sum_i a_i * b_i`.
+///
+/// Products are computed with `mul_lazy` and accumulated before a single `scale_and_round` and
+/// a single relinearization.
+pub fn sum_of_products(
+    evaluator: &Evaluator,
+    pairs: &[(&Ciphertext, &Ciphertext)],
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let mut products = pairs.iter().map(|(a, b)| evaluator.mul_lazy(a, b));
+    let mut sum = products.next().expect("No products to sum");
+    products.for_each(|p| evaluator.add_assign(&mut sum, &p));
+    let sum = evaluator.scale_and_round(&mut sum);
+    evaluator.relinearize(&sum, ek)
+}
+
+/// Returns `a * b` relinearized
+fn mul_relin(
+    evaluator: &Evaluator,
+    a: &Ciphertext,
+    b: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    sum_of_products(evaluator, &[(a, b)], ek)
+}
+
+/// Returns `ct + m` for plaintext `m`
+fn add_plain(evaluator: &Evaluator, ct: &Ciphertext, m: &[u64]) -> Ciphertext {
+    let pt = evaluator.plaintext_encode(
+        m,
+        Encoding::simd(
+            ct.level(),
+            PolyCache::AddSub(ct.c_ref()[0].representation().clone()),
+        ),
+    );
+    evaluator.add_plaintext(ct, &pt)
+}
+
+/// Returns `a` in slots where `cond` is 1 and `b` where it is 0.
+///
+/// Computed as `b + cond * (a - b)`. `a` and `b` can be arbitrary values, only `cond` must be 0/1.
+pub fn select(
+    evaluator: &Evaluator,
+    cond: &Ciphertext,
+    a: &Ciphertext,
+    b: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let a_minus_b = evaluator.sub(a, b);
+    let mut res = mul_relin(evaluator, cond, &a_minus_b, ek);
+    evaluator.add_assign(&mut res, b);
+    res
+}
+
+/// Returns `values[i]` in slots where `conds[i]` is 1, given that in every slot exactly one of
+/// `conds` is 1 (and 0 in slots where all `conds` are 0).
+///
+/// Computed as `\sum_i conds[i] * values[i]` with `sum_of_products`, hence costs a single
+/// relinearization irrespective of the number of choices.
+pub fn select_one_hot(
+    evaluator: &Evaluator,
+    conds: &[Ciphertext],
+    values: &[Ciphertext],
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    assert!(conds.len() == values.len());
+    let pairs = conds.iter().zip(values.iter()).collect::<Vec<_>>();
+    sum_of_products(evaluator, &pairs, ek)
+}
+
+/// Returns plaintext `m_a` in slots where `cond` is 1 and plaintext `m_b` where it is 0.
+pub fn select_plain(
+    evaluator: &Evaluator,
+    cond: &Ciphertext,
+    m_a: &[u64],
+    m_b: &[u64],
+) -> Ciphertext {
+    let modt = &evaluator.params().plaintext_modulus_op;
+    let mut a_minus_b = m_a.to_vec();
+    modt.sub_mod_fast_vec(&mut a_minus_b, m_b);
+    let res = mul_plain(evaluator, cond, &a_minus_b);
+    add_plain(evaluator, &res, m_b)
+}
+
+/// Returns `1 - a`
+pub fn not(evaluator: &Evaluator, a: &Ciphertext) -> Ciphertext {
+    one_minus(evaluator, a)
+}
+
+/// Returns `a * b`
+pub fn and(
+    evaluator: &Evaluator,
+    a: &Ciphertext,
+    b: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    mul_relin(evaluator, a, b, ek)
+}
+
+/// Returns `a * m` for plaintext `m`
+pub fn and_plain(evaluator: &Evaluator, a: &Ciphertext, m: &[u64]) -> Ciphertext {
+    mul_plain(evaluator, a, m)
+}
+
+/// Returns `a + b - a * b`
+pub fn or(evaluator: &Evaluator, a: &Ciphertext, b: &Ciphertext, ek: &EvaluationKey) -> Ciphertext {
+    let mut res = mul_relin(evaluator, a, b, ek);
+    evaluator.negate_assign(&mut res);
+    evaluator.add_assign(&mut res, a);
+    evaluator.add_assign(&mut res, b);
+    res
+}
+
+/// Returns `a * (1 - m) + m` for plaintext `m`
+pub fn or_plain(evaluator: &Evaluator, a: &Ciphertext, m: &[u64]) -> Ciphertext {
+    let modt = &evaluator.params().plaintext_modulus_op;
+    let mut one_minus_m = vec![1u64; m.len()];
+    modt.sub_mod_fast_vec(&mut one_minus_m, m);
+    let res = mul_plain(evaluator, a, &one_minus_m);
+    add_plain(evaluator, &res, m)
+}
+
+/// Returns `(a - b)^2`, which equals `a + b - 2ab` for 0/1 values
+pub fn xor(
+    evaluator: &Evaluator,
+    a: &Ciphertext,
+    b: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let a_minus_b = evaluator.sub(a, b);
+    mul_relin(evaluator, &a_minus_b, &a_minus_b, ek)
+}
+
+/// Returns `a * (1 - 2m) + m` for plaintext `m`
+pub fn xor_plain(evaluator: &Evaluator, a: &Ciphertext, m: &[u64]) -> Ciphertext {
+    let modt = &evaluator.params().plaintext_modulus_op;
+    let mut one_minus_2m = m.to_vec();
+    modt.scalar_mul_mod_fast_vec(&mut one_minus_2m, 2);
+    modt.neg_mod_fast_vec(&mut one_minus_2m);
+    one_minus_2m
+        .iter_mut()
+        .for_each(|v| *v = modt.add_mod_fast(*v, 1));
+    let res = mul_plain(evaluator, a, &one_minus_2m);
+    add_plain(evaluator, &res, m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, SecretKey};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn boolean_gates_work() {
+        let mut rng = thread_rng();
+        let params = BfvParameters::default(5, 1 << 4);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let degree = evaluator.params().degree;
+        let mut random_bits = || {
+            (0..degree)
+                .map(|_| rng.gen_range(0..2u64))
+                .collect::<Vec<u64>>()
+        };
+        let ma = random_bits();
+        let mb = random_bits();
+        let mc = random_bits();
+        let mx = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(degree, &mut rng);
+        let my = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(degree, &mut rng);
+
+        let encrypt = |m: &[u64], rng: &mut rand::rngs::ThreadRng| {
+            evaluator.encrypt(
+                &sk,
+                &evaluator.plaintext_encode(m, Encoding::default()),
+                rng,
+            )
+        };
+        let a = encrypt(&ma, &mut rng);
+        let b = encrypt(&mb, &mut rng);
+        let c = encrypt(&mc, &mut rng);
+        let x = encrypt(&mx, &mut rng);
+        let y = encrypt(&my, &mut rng);
+
+        let decrypt = |ct: &Ciphertext| -> Vec<u64> {
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default())
+        };
+        let expected = |f: &dyn Fn(u64, u64) -> u64| -> Vec<u64> {
+            ma.iter().zip(mb.iter()).map(|(a, b)| f(*a, *b)).collect()
+        };
+
+        assert_eq!(decrypt(&not(&evaluator, &a)), expected(&|a, _| 1 - a));
+        assert_eq!(
+            decrypt(&and(&evaluator, &a, &b, &ek)),
+            expected(&|a, b| a & b)
+        );
+        assert_eq!(
+            decrypt(&or(&evaluator, &a, &b, &ek)),
+            expected(&|a, b| a | b)
+        );
+        assert_eq!(
+            decrypt(&xor(&evaluator, &a, &b, &ek)),
+            expected(&|a, b| a ^ b)
+        );
+        assert_eq!(
+            decrypt(&and_plain(&evaluator, &a, &mb)),
+            expected(&|a, b| a & b)
+        );
+        assert_eq!(
+            decrypt(&or_plain(&evaluator, &a, &mb)),
+            expected(&|a, b| a | b)
+        );
+        assert_eq!(
+            decrypt(&xor_plain(&evaluator, &a, &mb)),
+            expected(&|a, b| a ^ b)
+        );
+
+        let expected_select = mc
+            .iter()
+            .enumerate()
+            .map(|(i, c)| if *c == 1 { mx[i] } else { my[i] })
+            .collect::<Vec<u64>>();
+        assert_eq!(
+            decrypt(&select(&evaluator, &c, &x, &y, &ek)),
+            expected_select
+        );
+        assert_eq!(
+            decrypt(&select_plain(&evaluator, &c, &mx, &my)),
+            expected_select
+        );
+
+        // one hot conditions `c * a`, `c * (1 - a)` and `1 - c`
+        let conds = [
+            and(&evaluator, &c, &a, &ek),
+            and(&evaluator, &c, &not(&evaluator, &a), &ek),
+            not(&evaluator, &c),
+        ];
+        let z = encrypt(&mb, &mut rng);
+        let expected_one_hot = (0..degree)
+            .map(|i| match (mc[i], ma[i]) {
+                (1, 1) => mx[i],
+                (1, _) => my[i],
+                _ => mb[i],
+            })
+            .collect::<Vec<u64>>();
+        assert_eq!(
+            decrypt(&select_one_hot(&evaluator, &conds, &[x, y, z], &ek)),
+            expected_one_hot
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use crate::utils::one_minus;
+use bfv::{Ciphertext, Encoding, EvaluationKey, Evaluator, PolyCache};
+
+/// Returns `x^exp` using square-and-multiply.
+///
+/// Requires `floor(log2(exp))` squarings and `popcount(exp) - 1` multiplications,
+/// each followed by relinearization. `exp` must be greater than 0.
+pub fn pow(evaluator: &Evaluator, x: &Ciphertext, exp: usize, ek: &EvaluationKey) -> Ciphertext {
+    assert!(exp > 0);
+
+    let mut base = x.clone();
+    let mut res: Option<Ciphertext> = None;
+    let mut exp = exp;
+    loop {
+        if exp & 1 == 1 {
+            res = match res {
+                Some(r) => Some(evaluator.relinearize(&evaluator.mul(&r, &base), ek)),
+                None => Some(base.clone()),
+            };
+        }
+        exp >>= 1;
+        if exp == 0 {
+            break;
+        }
+        base = evaluator.relinearize(&evaluator.mul(&base, &base), ek);
+    }
+
+    res.unwrap()
+}
+
+/// Returns `1 - z^(p-1)`, which is 1 for slots where `z = 0` and 0 otherwise (Fermat's little theorem)
+pub fn is_zero(evaluator: &Evaluator, z: &Ciphertext, ek: &EvaluationKey) -> Ciphertext {
+    let p = evaluator.params().plaintext_modulus;
+    let z_pow = pow(evaluator, z, (p - 1) as usize, ek);
+    one_minus(evaluator, &z_pow)
+}
+
+/// Returns encrypted 1 in slots where `x == y` and 0 otherwise.
+///
+/// Evaluates `1 - (x - y)^(p-1)`. For `p = 65537` this costs 16 squarings.
+pub fn equal(
+    evaluator: &Evaluator,
+    x: &Ciphertext,
+    y: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let z = evaluator.sub(x, y);
+    is_zero(evaluator, &z, ek)
+}
+
+/// Returns encrypted 1 in slots where `x` equals public value `m` and 0 otherwise.
+///
+/// Same as `equal` except that difference is computed with a plaintext subtraction, hence
+/// `m` does not need to be encrypted.
+pub fn equal_plaintext(
+    evaluator: &Evaluator,
+    x: &Ciphertext,
+    m: &[u64],
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let pt = evaluator.plaintext_encode(
+        m,
+        Encoding::simd(
+            x.level(),
+            PolyCache::AddSub(x.c_ref()[0].representation().clone()),
+        ),
+    );
+    let z = evaluator.sub_plaintext(x, &pt);
+    is_zero(evaluator, &z, ek)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, SecretKey};
+    use rand::thread_rng;
+
+    #[test]
+    fn equal_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 10], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let modt = &evaluator.params().plaintext_modulus_op;
+        let mx = modt.random_vec(evaluator.params().degree, &mut rng);
+        // make every other slot equal
+        let my = mx
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if i & 1 == 0 {
+                    *v
+                } else {
+                    modt.add_mod_fast(*v, 1)
+                }
+            })
+            .collect::<Vec<u64>>();
+        let expected = (0..evaluator.params().degree)
+            .map(|i| (i & 1 == 0) as u64)
+            .collect::<Vec<u64>>();
+
+        let x = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&mx, Encoding::default()),
+            &mut rng,
+        );
+        let y = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&my, Encoding::default()),
+            &mut rng,
+        );
+
+        let res_ct = equal(&evaluator, &x, &y, &ek);
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+        assert_eq!(res_m, expected);
+
+        let res_ct = equal_plaintext(&evaluator, &x, &my, &ek);
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+        assert_eq!(res_m, expected);
+    }
+}
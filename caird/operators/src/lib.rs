@@ -1,243 +1,248 @@
-use std::sync::Arc;
-
 use bfv::{
-    Ciphertext, Encoding, EvaluationKey, Evaluator, Modulus, Plaintext, PolyCache, PolyType,
-    Representation, SecretKey,
+    BfvParameters, Ciphertext, Encoding, EvaluationKey, Evaluator, Modulus, Plaintext, PolyCache,
+    PolyType, Representation,
 };
-use rand::thread_rng;
-use utils::{decrypt_and_print, read_values, store_values};
-
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+mod boolean;
+mod equality;
+mod matrix;
+mod min_max;
+mod polynomial;
+mod slots;
+mod sorting;
+mod univariate;
 pub mod utils;
-
+mod wide_int;
+
+pub use boolean::*;
+pub use equality::*;
+pub use matrix::*;
+pub use min_max::*;
+pub use polynomial::*;
+pub use slots::*;
+pub use sorting::*;
+pub use univariate::*;
+pub use wide_int::*;
+
+/// Returns `x, x^2, ..., x^max` in `representation`.
+///
+/// Powers are built in a balanced tree: `x^i = x^(2^(k-1)) * x^(i - 2^(k-1))` for
+/// `2^(k-1) < i <= 2^k`, thus multiplicative depth of `x^i` is `ceil(log2(i))`, which is the
+/// minimum possible. Powers in the same range `(2^(k-1), 2^k]` only depend on lower powers, hence
+/// they are computed in parallel if `parallel` feature is enabled.
 pub fn powers_of_x(
     evaluator: &Evaluator,
     x: &Ciphertext,
     max: usize,
-    sk: &SecretKey,
+    representation: Representation,
     ek: &EvaluationKey,
 ) -> Vec<Ciphertext> {
-    let dummy = Ciphertext::new(vec![], PolyType::Q, 0);
-    let mut values = vec![dummy; max];
-    let mut calculated = vec![0u64; max];
-    values[0] = x.clone();
-    calculated[0] = 1;
-    // let mut mul_count = 0;
-
-    for i in (2..(max + 1)).rev() {
-        let mut exp = i;
-        let mut base_deg = 1;
-        let mut res_deg = 0;
-
-        while exp > 0 {
-            if exp & 1 == 1 {
-                let p_res_deg = res_deg;
-                res_deg += base_deg;
-                if res_deg != base_deg && calculated[res_deg - 1] == 0 {
-                    let tmp = evaluator.mul(&values[p_res_deg - 1], &values[base_deg - 1]);
-                    values[res_deg - 1] = evaluator.relinearize(&tmp, ek);
-                    // println!("Res deg time: {:?}", now.elapsed());
-                    calculated[res_deg - 1] = 1;
-                    // mul_count += 1;
-                }
-            }
-            exp >>= 1;
-            if exp != 0 {
-                let p_base_deg = base_deg;
-                base_deg *= 2;
-                if calculated[base_deg - 1] == 0 {
-                    let tmp = evaluator.mul(&values[p_base_deg - 1], &values[p_base_deg - 1]);
-                    values[base_deg - 1] = evaluator.relinearize(&tmp, ek);
-
-                    calculated[base_deg - 1] = 1;
+    assert!(max > 0);
+
+    let mut values = Vec::with_capacity(max);
+    values.push(x.clone());
+
+    let mut lower = 1;
+    while lower < max {
+        let upper = std::cmp::min(2 * lower, max);
+
+        // x^i = x^lower * x^(i - lower)
+        let compute = |i: usize| {
+            let tmp = evaluator.mul(&values[lower - 1], &values[i - lower - 1]);
+            evaluator.relinearize(&tmp, ek)
+        };
+        #[cfg(feature = "parallel")]
+        let next = ((lower + 1)..=upper)
+            .into_par_iter()
+            .map(compute)
+            .collect::<Vec<Ciphertext>>();
+        #[cfg(not(feature = "parallel"))]
+        let next = ((lower + 1)..=upper)
+            .map(compute)
+            .collect::<Vec<Ciphertext>>();
+
+        values.extend(next);
+        lower = upper;
+    }
 
-                    // mul_count += 1;
-                }
-            }
-        }
+    if representation == Representation::Evaluation {
+        #[cfg(feature = "parallel")]
+        let iter = values.par_iter_mut();
+        #[cfg(not(feature = "parallel"))]
+        let iter = values.iter_mut();
+        iter.for_each(|c| {
+            evaluator.ciphertext_change_representation(c, Representation::Evaluation);
+        });
     }
-    // dbg!(mul_count);
 
     values
 }
 
-pub fn sort(
-    evaluator: &Evaluator,
-    values: &[Ciphertext],
-    ek: &EvaluationKey,
-    sk: &SecretKey,
-) -> Vec<Ciphertext> {
-    let mut ht = vec![Ciphertext::placeholder(); values.len()];
-
-    let one = evaluator.plaintext_encode(
-        &vec![1; evaluator.params().degree],
-        Encoding::simd(0, PolyCache::AddSub(Representation::Coefficient)),
-    );
-
-    println!("Sorting ciphertext ~~~~~~~~~");
-
-    for i in 0..values.len() {
-        for j in 0..values.len() {
-            if i < j {
-                let lt = univariate_less_than(evaluator, &values[i], &values[j], ek, sk);
-
-                let mut one_minus_lt = evaluator.negate(&lt);
-                evaluator.add_assign_plaintext(&mut one_minus_lt, &one);
-
-                // add lt to ht[i]
-                if ht[i].c_ref().len() != 0 {
-                    evaluator.add_assign(&mut ht[i], &lt);
-                } else {
-                    ht[i] = lt;
-                }
-
-                // add 1 - lt to ht[j]
-                if ht[j].c_ref().len() != 0 {
-                    evaluator.add_assign(&mut ht[j], &one_minus_lt);
-                } else {
-                    ht[j] = one_minus_lt;
-                }
-            }
-        }
-    }
-
-    // equality checks
+/// Returns inclusive range `(lower, upper)` of signed values (see `Plaintext::encode_signed`) that
+/// can be compared with `univariate_less_than` and friends.
+///
+/// Comparison is only correct if difference of the inputs lies in `[-(t-1)/2, (t-1)/2]`. Unsigned inputs
+/// satisfy this when they lie in `[0, t/2)`, whereas signed inputs must lie in `[-(t-1)/4, (t-1)/4]`.
+pub fn signed_comparison_range(params: &BfvParameters) -> (i64, i64) {
+    let bound = ((params.plaintext_modulus - 1) / 4) as i64;
+    (-bound, bound)
+}
 
-    // precompute powers
-    let mut ht_powers = vec![];
-    ht.iter().for_each(|c| {
-        // change ciphertexts to Evaluation representation for plaintext multiplication
-        let mut powers = powers_of_x(evaluator, c, 65536, sk, ek);
-        powers.iter_mut().for_each(|c| {
-            evaluator.ciphertext_change_representation(c, Representation::Evaluation);
-        });
-        ht_powers.push(powers);
+/// Encodes signed `m` for comparison with `univariate_less_than` and friends.
+///
+/// Same as `Evaluator::plaintext_encode_signed` except that it panics if any value lies outside
+/// of `signed_comparison_range`, since such values encode fine but compare incorrectly.
+pub fn encode_signed_comparable(evaluator: &Evaluator, m: &[i64], encoding: Encoding) -> Plaintext {
+    let (lower, upper) = signed_comparison_range(evaluator.params());
+    m.iter().for_each(|v| {
+        assert!(
+            lower <= *v && *v <= upper,
+            "Value {v} outside of signed comparison range [{lower}, {upper}]"
+        );
     });
-
-    println!("Equality checks ~~~~~~~~~");
-
-    let mut sorted_values = vec![];
-
-    for i in 0..values.len() {
-        // get `i_th` ciphertext in descending order
-        sorted_values.push(sort_equality_subroutine(
-            evaluator, i, &ht_powers, values, sk, ek,
-        ));
-    }
-
-    println!("Sorting ciphertext done!! ~~~~~~~~~");
-
-    sorted_values
+    evaluator.plaintext_encode_signed(m, encoding)
 }
 
-/// Returns ciphertext with hamming weight = `i`
-pub fn sort_equality_subroutine(
+/// Returns encrypted 1 in slots where `x < y` and 0 otherwise.
+///
+/// Both inputs must either be unsigned values in `[0, t/2)` or signed values in `signed_comparison_range`
+/// (see `encode_signed_comparable`). Inputs are encrypted, thus cannot be checked here and
+/// inputs outside of the range silently produce wrong results.
+pub fn univariate_less_than(
     evaluator: &Evaluator,
-    i: usize,
-    ht_powers: &[Vec<Ciphertext>],
-    values: &[Ciphertext],
-    sk: &SecretKey,
+    x: &Ciphertext,
+    y: &Ciphertext,
+    lt: &LessThanCoefficients,
     ek: &EvaluationKey,
 ) -> Ciphertext {
-    let p = 65537;
-    let modp = &evaluator.params().plaintext_modulus_op;
+    let z = evaluator.sub(x, y);
+    univariate_less_than_zero(evaluator, &z, lt, ek)
+}
 
-    let one_pt = evaluator.plaintext_encode(
-        &vec![1; evaluator.params().degree],
-        Encoding::simd(0, PolyCache::AddSub(Representation::Evaluation)),
-    );
+/// Returns encrypted 1 in slots where `x` is less than public value `m` and 0 otherwise.
+///
+/// Same as `univariate_less_than` except that the difference is computed with a plaintext
+/// subtraction, hence `m` does not need to be encrypted. Both `x` and `m` must lie in `[0, t/2)`.
+///
+/// Panics if any value of `m` lies outside of `[0, t/2)`.
+pub fn less_than_plain(
+    evaluator: &Evaluator,
+    x: &Ciphertext,
+    m: &[u64],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let pt = encode_unsigned_comparable(evaluator, x, m);
+    // x - m
+    let z = evaluator.sub_plaintext(x, &pt);
+    univariate_less_than_zero(evaluator, &z, lt, ek)
+}
 
-    let n = values.len();
-    let mut i_pow_k = 1;
-    let mut sum = vec![Ciphertext::placeholder(); values.len()];
-    for k in 0..p {
-        // i^0 = 1, so we don't need plaintext multiplication
-        if k == 0 {
-            for j in 0..n {
-                sum[j] = ht_powers[j][p - 1 - (k + 1)].clone();
-            }
-        } else if k == p - 1 {
-            // x^((p-1)-(p-1)) = x^0 = 1; We can ignore the ciphertext
-            // and add 1 depending on value of `i`.
-            // Since `i^(p-1) = 1` if i!=0, and 0 otherwise, we add 1
-            // to sum when i != 0.
-            if i != 0 {
-                for j in 0..n {
-                    evaluator.add_assign_plaintext(&mut sum[j], &one_pt);
-                }
-            }
-        } else {
-            // if i == 0, then i^k == 0 for k > 0 always. Thus plaintext multiplication
-            // will always result in 0 ciphertext. So skip this part when i==0.
-            if i != 0 {
-                let pt = evaluator.plaintext_encode(
-                    &vec![i_pow_k; evaluator.params().degree],
-                    Encoding::simd(0, PolyCache::Mul(PolyType::Q)),
-                );
-                for j in 0..n {
-                    evaluator.add_assign(
-                        &mut sum[j],
-                        &evaluator.mul_plaintext(&ht_powers[j][p - 1 - (k + 1)], &pt),
-                    );
-                }
-            }
-        }
+/// Returns encrypted 1 in slots where `x` is greater than public value `m` and 0 otherwise.
+///
+/// See `less_than_plain`.
+pub fn greater_than_plain(
+    evaluator: &Evaluator,
+    x: &Ciphertext,
+    m: &[u64],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let pt = encode_unsigned_comparable(evaluator, x, m);
+    // m - x
+    let mut z = evaluator.sub_plaintext(x, &pt);
+    evaluator.negate_assign(&mut z);
+    univariate_less_than_zero(evaluator, &z, lt, ek)
+}
 
-        i_pow_k = modp.mul_mod_fast(i_pow_k, i as u64);
-    }
+/// Encodes public operand `m` of plaintext comparisons with `x` for subtraction from `x`.
+/// Panics if any value lies outside of `[0, t/2)`.
+fn encode_unsigned_comparable(evaluator: &Evaluator, x: &Ciphertext, m: &[u64]) -> Plaintext {
+    let half = evaluator.params().plaintext_modulus / 2;
+    m.iter().for_each(|v| {
+        assert!(
+            *v < half,
+            "Value {v} outside of comparison range [0, {half})"
+        );
+    });
+    evaluator.plaintext_encode(
+        m,
+        Encoding::simd(
+            x.level(),
+            PolyCache::AddSub(x.c_ref()[0].representation().clone()),
+        ),
+    )
+}
 
-    // 1 - sum[j]
-    for j in 0..n {
-        evaluator.negate_assign(&mut sum[j]);
-        evaluator.add_assign_plaintext(&mut sum[j], &one_pt);
-    }
+/// Coefficients of `g` evaluated by `univariate_less_than` and friends (see
+/// `compute_lt_coefficients`).
+///
+/// Computing coefficients costs `O(t log t)`, thus they should be computed once (for ex, at
+/// startup) and reused for every comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LessThanCoefficients {
+    t: u64,
+    coefficients: Vec<u64>,
+}
 
-    // sum[j] indicates whether hw of `j^th` ciphertext equals `i`. It's
-    // 1 if it does, otherwise 0. We multiply `j^th` ciphertext by `sum[j]` (which is 0 or 1).
-    // Each product copies over values from `j^th` ct only if ct's hw is `i`.
-    // Summation of all products should only contain values of ciphertexts corresponding to hw = `i`
-    // \sum_{j=0}^{N-1} sum[j] * values[j]
-    let mut sum_all = Ciphertext::placeholder();
-    for j in 0..n {
-        // Since `sum[j]` is in Evaluation form and `values[j]` is in Coefficient, pass
-        // `sum[j]` as the first operand.
-        let product = evaluator.mul_lazy(&sum[j], &values[j]);
-
-        if j == 0 {
-            sum_all = product;
-        } else {
-            evaluator.add_assign(&mut sum_all, &product);
+impl LessThanCoefficients {
+    pub fn new(params: &BfvParameters) -> LessThanCoefficients {
+        LessThanCoefficients {
+            t: params.plaintext_modulus,
+            coefficients: compute_lt_coefficients(params.plaintext_modulus),
         }
     }
-
-    let res = evaluator.scale_and_round(&mut sum_all);
-    evaluator.relinearize(&res, ek)
 }
 
-pub fn univariate_less_than(
+/// Returns encrypted 1 in slots where `z`, interpreted as difference `x - y` of two values in `[0, t/2)`,
+/// is negative and 0 otherwise.
+///
+/// Evaluates `((t+1)/2) * z^(t-1) + z * g(z^2)`, where `g` has `n = (t-1)/2` coefficients given by
+/// `lt`. `g(x)` is evaluated with baby-step giant-step: `x, ..., x^b` for `b = floor(sqrt(n))`
+/// and `x^b, ..., (x^b)^(ceil(n/b) - 1)`, which also give `z^(t-1) = x^n`.
+fn univariate_less_than_zero(
     evaluator: &Evaluator,
-    x: &Ciphertext,
-    y: &Ciphertext,
+    z: &Ciphertext,
+    lt: &LessThanCoefficients,
     ek: &EvaluationKey,
-    sk: &SecretKey,
 ) -> Ciphertext {
-    let z = evaluator.sub(x, y);
-    let z_sq = evaluator.relinearize(&evaluator.mul(&z, &z), ek);
-
-    // z^2..(z^2)^181
-    let mut m_powers = powers_of_x(evaluator, &z_sq, 181, sk, ek);
-    // (z^2)^181..((z^2)^181)^181
-    let k_powers = powers_of_x(evaluator, &m_powers[180], 181, sk, ek);
+    let t = evaluator.params().plaintext_modulus;
+    assert!(
+        lt.t == t,
+        "Coefficients computed for t={} instead of t={t}",
+        lt.t
+    );
 
-    // decrypt_and_print(evaluator, &m_powers[180], sk, "m_powers[180]");
-    // decrypt_and_print(evaluator, &k_powers[180], sk, "k_powers[180]");
+    let degree = evaluator.params().degree;
+    let level = z.level();
+    let n = lt.coefficients.len();
+    let baby = (1..=n).take_while(|b| b * b <= n).last().unwrap();
+    let giant = (n + baby - 1) / baby;
+    assert!(giant > 1, "Plaintext modulus {t} is too small");
+
+    let z_sq = evaluator.relinearize(&evaluator.mul(z, z), ek);
+
+    // x^1..x^baby, where x = z^2
+    let mut m_powers = powers_of_x(evaluator, &z_sq, baby, Representation::Coefficient, ek);
+    // (x^baby)^1..(x^baby)^(giant-1)
+    let k_powers = powers_of_x(
+        evaluator,
+        &m_powers[baby - 1],
+        giant - 1,
+        Representation::Coefficient,
+        ek,
+    );
 
-    // ((z^2)^181)^181 * (z^2)^7 = z^65536; z^{p-1}
-    let mut z_max_lazy = evaluator.mul_lazy(&k_powers[180], &m_powers[6]);
+    // x^n = (x^baby)^(giant-1) * x^(n - baby*(giant-1)) = z^(t-1)
+    let mut z_max_lazy =
+        evaluator.mul_lazy(&k_powers[giant - 2], &m_powers[n - baby * (giant - 1) - 1]);
     {
-        // coefficient for z^65536 = (p+1)/2
+        // coefficient for z^(t-1) = (t+1)/2
         let pt = evaluator.plaintext_encode(
-            &vec![32769; evaluator.params().degree],
-            Encoding::simd(0, PolyCache::Mul(PolyType::PQ)),
+            &vec![(t + 1) / 2; degree],
+            Encoding::simd(level, PolyCache::Mul(PolyType::PQ)),
         );
         evaluator.mul_poly_assign(&mut z_max_lazy, pt.mul_poly_ref());
     }
@@ -247,31 +252,28 @@ pub fn univariate_less_than(
         evaluator.ciphertext_change_representation(x, Representation::Evaluation);
     });
 
-    let coefficients = read_values("less_than.bin");
-
-    // evaluate g(x), where x = z^2
+    // evaluate g(x)
     let mut left_over = Ciphertext::placeholder();
     let mut sum_k = Ciphertext::placeholder();
-    for k_index in 0..182 {
-        // m loop calculates x^0 + x + ... + x^181
+    for k_index in 0..giant {
+        // m loop calculates x^0 + x + ... + x^(baby-1)
         let mut x_0_pt = None;
         let mut sum_m = Ciphertext::placeholder();
-        for m_index in 0..181 {
-            // degree of g(x) is (65537 - 3) / 2
-            // dbg!(181 * k_index + m_index);
-            if 181 * k_index + m_index <= ((65537 - 3) / 2) {
-                let alpha = coefficients[(181 * k_index) + m_index];
+        for m_index in 0..baby {
+            // g(x) has n coefficients
+            if baby * k_index + m_index < n {
+                let alpha = lt.coefficients[(baby * k_index) + m_index];
 
                 if m_index == 0 {
                     let pt_alpha = evaluator.plaintext_encode(
-                        &vec![alpha; evaluator.params().degree],
-                        Encoding::simd(0, PolyCache::AddSub(Representation::Evaluation)),
+                        &vec![alpha; degree],
+                        Encoding::simd(level, PolyCache::AddSub(Representation::Evaluation)),
                     );
                     x_0_pt = Some(pt_alpha);
                 } else {
                     let pt_alpha = evaluator.plaintext_encode(
-                        &vec![alpha; evaluator.params().degree],
-                        Encoding::simd(0, PolyCache::Mul(PolyType::Q)),
+                        &vec![alpha; degree],
+                        Encoding::simd(level, PolyCache::Mul(PolyType::Q)),
                     );
                     if m_index == 1 {
                         sum_m = evaluator.mul_poly(&m_powers[m_index - 1], pt_alpha.mul_poly_ref());
@@ -308,9 +310,9 @@ pub fn univariate_less_than(
     evaluator.add_assign(&mut sum_k, &left_over);
 
     // z * g(z^2)
-    let z_gx = evaluator.mul_lazy(&sum_k, &z);
+    let z_gx = evaluator.mul_lazy(&sum_k, z);
 
-    // ((p+1)/2)z + z * g(z^2)
+    // ((t+1)/2)z^(t-1) + z * g(z^2)
     evaluator.add_assign(&mut z_max_lazy, &z_gx);
 
     let res = evaluator.scale_and_round(&mut z_max_lazy);
@@ -319,40 +321,63 @@ pub fn univariate_less_than(
     res
 }
 
-/// \alpha_i = \sum_{a = 1}^{\frac{p-1}{2}} a^{p - 1 - i}
+/// Returns coefficients `\alpha_j = \sum_{a = 1}^{\frac{t-1}{2}} a^{t - 2 - 2j}` of `g` for
+/// `j` in `0..(t-1)/2` (see `univariate_less_than_zero`).
+///
+/// Since `a^{t - 2 - 2j} = a^{-(2j+1)}`, `\alpha_j` is inverse power sum `2j+1` of indicator
+/// of `[1, (t-1)/2]`, thus all coefficients are computed at once with `inverse_power_sums`.
 pub fn compute_lt_coefficients(t: u64) -> Vec<u64> {
     let modt = Modulus::new(t);
 
-    println!("Computing alpha vector!! ~~~~~~~~~");
-    println!("t = {}", t);
-
-    let mut alpha_vec = vec![];
-
-    for i in 0..(t - 3 + 1) {
-        // only when even
-        if i & 1 == 0 {
-            let mut alpha = 0;
+    let indicator = (0..t)
+        .map(|a| (1..=(t - 1) / 2).contains(&a) as u64)
+        .collect::<Vec<u64>>();
+    let sums = inverse_power_sums(&indicator, &modt);
 
-            for a in 1..((t - 1) / 2) + 1 {
-                alpha = modt.add_mod_fast(alpha, modt.exp(a, (t - 1 - (i + 1)) as usize));
-            }
-            alpha_vec.push(alpha);
-        }
-        println!("Reached i = {}", i);
-    }
-    println!("Alpha vector!! ~~~~~~~~~");
-    println!("alpha_vec.len() = {}", alpha_vec.len());
-
-    store_values(&alpha_vec, "less_than.bin");
-
-    alpha_vec
+    sums.iter().skip(1).step_by(2).copied().collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use bfv::BfvParameters;
-    use rand::thread_rng;
+    use bfv::SecretKey;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn powers_of_x_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 10], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let modt = &evaluator.params().plaintext_modulus_op;
+        let m = modt.random_vec(evaluator.params().degree, &mut rng);
+        let x = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&m, Encoding::default()),
+            &mut rng,
+        );
+
+        let max = 37;
+        let powers = powers_of_x(&evaluator, &x, max, Representation::Evaluation, &ek);
+        assert_eq!(powers.len(), max);
+
+        let mut expected = m.clone();
+        for (i, power) in powers.iter().enumerate() {
+            assert_eq!(
+                power.c_ref()[0].representation(),
+                &Representation::Evaluation
+            );
+            let res_m =
+                evaluator.plaintext_decode(&evaluator.decrypt(&sk, power), Encoding::default());
+            assert_eq!(res_m, expected, "x^{}", i + 1);
+            modt.mul_mod_fast_vec(&mut expected, &m);
+        }
+    }
 
     #[test]
     fn less_than_works() {
@@ -367,29 +392,137 @@ mod tests {
         let mx = modt_by_2.random_vec(params.degree, &mut rng);
         let my = modt_by_2.random_vec(params.degree, &mut rng);
 
-        println!("mx: {:?}", mx);
-        println!("my: {:?}", my);
-
         let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
 
         let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
 
         let ptx = evaluator.plaintext_encode(&mx, Encoding::default());
         let pty = evaluator.plaintext_encode(&my, Encoding::default());
         let x = evaluator.encrypt(&sk, &ptx, &mut rng);
         let y = evaluator.encrypt(&sk, &pty, &mut rng);
-        let mut res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-        res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-        res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-        // res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-        // res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-        // res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-        // res_ct = univariate_less_than(&evaluator, &x, &y, &ek, &sk);
-
-        
+        let res_ct = univariate_less_than(&evaluator, &x, &y, &lt, &ek);
+
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+        let expected = mx
+            .iter()
+            .zip(my.iter())
+            .map(|(x, y)| if x < y { 1 } else { 0 })
+            .collect::<Vec<u64>>();
+        assert_eq!(res_m, expected);
+    }
+
+    #[test]
+    fn less_than_works_for_any_plaintext_modulus_and_level() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 8], 257, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let modt_by_2 = Modulus::new(params.plaintext_modulus / 2);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let mx = modt_by_2.random_vec(evaluator.params().degree, &mut rng);
+        let my = modt_by_2.random_vec(evaluator.params().degree, &mut rng);
+        let mut encrypt = |m: &[u64]| {
+            let mut ct = evaluator.encrypt(
+                &sk,
+                &evaluator.plaintext_encode(m, Encoding::default()),
+                &mut rng,
+            );
+            evaluator.mod_down_level(&mut ct, 1);
+            ct
+        };
+        let x = encrypt(&mx);
+        let y = encrypt(&my);
+
+        let res_ct = univariate_less_than(&evaluator, &x, &y, &lt, &ek);
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+        let expected = mx
+            .iter()
+            .zip(my.iter())
+            .map(|(x, y)| if x < y { 1 } else { 0 })
+            .collect::<Vec<u64>>();
+        assert_eq!(res_m, expected);
+    }
+
+    #[test]
+    fn less_than_plain_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 10], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let modt_by_2 = Modulus::new(params.plaintext_modulus / 2);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let mx = modt_by_2.random_vec(params.degree, &mut rng);
+        let bound = modt_by_2.random_vec(params.degree, &mut rng);
+
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let ptx = evaluator.plaintext_encode(&mx, Encoding::default());
+        let x = evaluator.encrypt(&sk, &ptx, &mut rng);
+
+        let res_ct = less_than_plain(&evaluator, &x, &bound, &lt, &ek);
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+        let expected = mx
+            .iter()
+            .zip(bound.iter())
+            .map(|(x, b)| if x < b { 1 } else { 0 })
+            .collect::<Vec<u64>>();
+        assert_eq!(res_m, expected);
+
+        let res_ct = greater_than_plain(&evaluator, &x, &bound, &lt, &ek);
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+        let expected = mx
+            .iter()
+            .zip(bound.iter())
+            .map(|(x, b)| if x > b { 1 } else { 0 })
+            .collect::<Vec<u64>>();
+        assert_eq!(res_m, expected);
+    }
+
+    #[test]
+    fn less_than_signed_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 10], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let (lower, upper) = signed_comparison_range(&params);
+        let mx = (0..params.degree)
+            .map(|_| rng.gen_range(lower..=upper))
+            .collect::<Vec<i64>>();
+        let my = (0..params.degree)
+            .map(|_| rng.gen_range(lower..=upper))
+            .collect::<Vec<i64>>();
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let ptx = encode_signed_comparable(&evaluator, &mx, Encoding::default());
+        let pty = encode_signed_comparable(&evaluator, &my, Encoding::default());
+        let x = evaluator.encrypt(&sk, &ptx, &mut rng);
+        let y = evaluator.encrypt(&sk, &pty, &mut rng);
+
+        let res_ct = univariate_less_than(&evaluator, &x, &y, &lt, &ek);
         let res_m =
             evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
-        println!("res_m: {:?}", res_m);
         let expected = mx
             .iter()
             .zip(my.iter())
@@ -398,54 +531,13 @@ mod tests {
         assert_eq!(res_m, expected);
     }
 
-    // #[test]
-    // fn sort_univariate_works() {
-    //     let mut rng = thread_rng();
-
-    //     let mut params = BfvParameters::new(&[60; 15], 65537, 1 << 3);
-    //     params.enable_hybrid_key_switching(&[60; 3]);
-
-    //     let sk = SecretKey::random_with_params(&params, &mut rng);
-
-    //     let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
-
-    //     let evaluator = Evaluator::new(params);
-    //     let max = 5;
-    //     let modt_by_2 = Modulus::new(evaluator.params().plaintext_modulus / 2);
-    //     let m_values = (0..max)
-    //         .into_iter()
-    //         .map(|_| modt_by_2.random_vec(evaluator.params().degree, &mut rng))
-    //         .collect::<Vec<Vec<u64>>>();
-    //     let values = m_values
-    //         .iter()
-    //         .map(|i| {
-    //             let pt = evaluator.plaintext_encode(i, Encoding::default());
-    //             evaluator.encrypt(&sk, &pt, &mut rng)
-    //         })
-    //         .collect::<Vec<Ciphertext>>();
-
-    //     let sorted_values = sort(&evaluator, &values, &ek, &sk);
-    //     dbg!(evaluator.measure_noise(&sk, &sorted_values[0]));
-
-    //     let m_sorted = sorted_values
-    //         .iter()
-    //         .map(|c| evaluator.plaintext_decode(&evaluator.decrypt(&sk, &c), Encoding::default()))
-    //         .collect::<Vec<Vec<u64>>>();
-
-    //     // check
-    //     let row_size = evaluator.params().degree;
-
-    //     for row_index in 0..row_size {
-    //         let mut row_values = vec![];
-    //         let mut row_sorted = vec![];
-    //         for col_index in 0..max {
-    //             row_values.push(m_values[col_index][row_index]);
-    //             row_sorted.push(m_sorted[col_index][row_index]);
-    //         }
-
-    //         row_values.sort_by(|a, b| b.cmp(a));
-
-    //         assert_eq!(row_values, row_sorted);
-    //     }
-    // }
+    #[test]
+    #[should_panic]
+    fn encode_signed_comparable_rejects_values_outside_of_range() {
+        let params = BfvParameters::new(&[60; 3], 65537, 1 << 4);
+        let (_, upper) = signed_comparison_range(&params);
+        let evaluator = Evaluator::new(params);
+        // encodes with `plaintext_encode_signed` but does not compare correctly
+        encode_signed_comparable(&evaluator, &[upper + 1], Encoding::default());
+    }
 }
@@ -0,0 +1,182 @@
+use crate::utils::constant_like;
+use bfv::{Ciphertext, Encoding, EvaluationKey, Evaluator, PolyCache, PolyType, Representation};
+
+/// Returns baby step size for row size `d`, ie smallest power of two `b` such that `b^2 >= d`
+fn baby_step_size(d: usize) -> usize {
+    let mut b = 1;
+    while b * b < d {
+        b <<= 1;
+    }
+    b
+}
+
+/// Returns rotation indices required by `matvec`: baby steps `1..b` and giant steps
+/// `b, 2b, ..., d - b`, where `d = degree/2` is the row size and `b = sqrt(d)` (rounded up to a
+/// power of two). Galois keys for these indices must be present in `EvaluationKey` at the level of
+/// the input ciphertext.
+pub fn matvec_rotation_indices(degree: usize) -> Vec<isize> {
+    let d = degree / 2;
+    let b = baby_step_size(d);
+    let mut indices = (1..b).map(|j| j as isize).collect::<Vec<isize>>();
+    indices.extend((1..(d / b)).map(|g| (g * b) as isize));
+    indices
+}
+
+/// Returns `M * v` for public matrix `M` and encrypted vector `v`.
+///
+/// Slots of `ct` are viewed as a `2 x (degree/2)` matrix, where each row holds a vector `v` in its
+/// first `n` slots (and 0s in the remaining). `M` is applied to both rows independently and `i^th`
+/// slot of each output row contains `i^th` element of `M * v`. `matrix` must have at most
+/// `degree/2` rows and at most `degree/2` columns, and is padded with zeros to `d x d` where
+/// `d = degree/2`.
+///
+/// Uses diagonal method of Halevi-Shoup, `M * v = \sum_k diag_k * rot_k(v)`, where
+/// `diag_k[i] = M[i][i + k]`, combined with baby-step giant-step: for `k = g * b + j`,
+/// `diag_k * rot_k(v) = rot_{gb}(rot_{-gb}(diag_k) * rot_j(v))`. Thus only `b - 1` rotations of
+/// `v` (baby steps, hoisted with `Evaluator::rotate_many`) and `d/b - 1` rotations of inner sums
+/// (giant steps) are required. Zero diagonals are skipped.
+pub fn matvec(
+    evaluator: &Evaluator,
+    matrix: &[Vec<u64>],
+    ct: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let degree = evaluator.params().degree;
+    let d = degree / 2;
+    assert!(!matrix.is_empty() && matrix.len() <= d);
+    assert!(matrix.iter().all(|row| row.len() <= d));
+
+    let element =
+        |i: usize, j: usize| -> u64 { matrix.get(i).and_then(|r| r.get(j)).map_or(0, |v| *v) };
+
+    let b = baby_step_size(d);
+    let giant_count = d / b;
+
+    // rot_j(v) for j in 0..b in Evaluation representation for plaintext multiplications. All baby
+    // steps rotate the same ciphertext, thus are hoisted.
+    let baby_indices = (1..b).map(|j| j as isize).collect::<Vec<isize>>();
+    let mut baby_steps = vec![ct.clone()];
+    baby_steps.extend(evaluator.rotate_many(ct, &baby_indices, ek));
+    baby_steps.iter_mut().for_each(|c| {
+        evaluator.ciphertext_change_representation(c, Representation::Evaluation);
+    });
+
+    let mut res: Option<Ciphertext> = None;
+    for g in 0..giant_count {
+        let mut inner: Option<Ciphertext> = None;
+        for j in 0..b {
+            let k = g * b + j;
+
+            // rot_{-gb}(diag_k)[i] = diag_k[i - gb] = M[i - gb][i - gb + k]
+            let row = (0..d)
+                .map(|i| {
+                    let r = (i + d - g * b) % d;
+                    element(r, (r + k) % d)
+                })
+                .collect::<Vec<u64>>();
+            if row.iter().all(|v| *v == 0) {
+                continue;
+            }
+
+            // same diagonal for both rows
+            let mut m = row.clone();
+            m.extend_from_slice(&row);
+            let pt = evaluator
+                .plaintext_encode(&m, Encoding::simd(ct.level(), PolyCache::Mul(PolyType::Q)));
+            let product = evaluator.mul_plaintext(&baby_steps[j], &pt);
+            match inner.as_mut() {
+                Some(s) => evaluator.add_assign(s, &product),
+                None => inner = Some(product),
+            }
+        }
+
+        // inner sums stay in Evaluation representation, since rotation preserves representation
+        let mut inner = match inner {
+            Some(s) => s,
+            None => continue,
+        };
+        if g != 0 {
+            inner = evaluator.rotate(&inner, (g * b) as isize, ek);
+        }
+
+        match res.as_mut() {
+            Some(r) => evaluator.add_assign(r, &inner),
+            None => res = Some(inner),
+        }
+    }
+
+    match res {
+        Some(mut res) => {
+            evaluator.ciphertext_change_representation(&mut res, Representation::Coefficient);
+            res
+        }
+        None => constant_like(evaluator, ct, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, SecretKey};
+    use rand::thread_rng;
+
+    #[test]
+    fn matvec_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 5], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let rtg_indices = matvec_rotation_indices(params.degree);
+        let ek = EvaluationKey::new(
+            &params,
+            &sk,
+            &[],
+            &vec![0; rtg_indices.len()],
+            &rtg_indices,
+            &mut rng,
+        );
+        let evaluator = Evaluator::new(params);
+
+        let degree = evaluator.params().degree;
+        let d = degree / 2;
+        let modt = &evaluator.params().plaintext_modulus_op;
+
+        // 5 x 7 matrix applied to two vectors of length 7
+        let (rows, cols) = (5, 7);
+        let matrix = (0..rows)
+            .map(|_| modt.random_vec(cols, &mut rng))
+            .collect::<Vec<Vec<u64>>>();
+        let vectors = (0..2)
+            .map(|_| modt.random_vec(cols, &mut rng))
+            .collect::<Vec<Vec<u64>>>();
+
+        let mut m = vec![0u64; degree];
+        for (r, v) in vectors.iter().enumerate() {
+            m[(r * d)..(r * d + cols)].copy_from_slice(v);
+        }
+        let ct = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&m, Encoding::default()),
+            &mut rng,
+        );
+
+        let res_ct = matvec(&evaluator, &matrix, &ct, &ek);
+        let res_m =
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+
+        for (r, v) in vectors.iter().enumerate() {
+            for i in 0..d {
+                let expected = if i < rows {
+                    matrix[i].iter().zip(v.iter()).fold(0, |acc, (a, b)| {
+                        modt.add_mod_fast(acc, modt.mul_mod_fast(*a, *b))
+                    })
+                } else {
+                    0
+                };
+                assert_eq!(res_m[r * d + i], expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{and, not, select, univariate_less_than, utils::one_like, LessThanCoefficients};
+use bfv::{Ciphertext, EvaluationKey, Evaluator};
+
+/// A node of the tournament tree. `selectors` holds one entry per leaf under the node
+/// and is `None` for a leaf that hasn't played yet (ie its selector is the constant 1).
+struct Node {
+    value: Ciphertext,
+    selectors: Vec<Option<Ciphertext>>,
+}
+
+/// Multiplies a selector with encrypted bit `c`. Missing selector is treated as 1.
+fn mul_selector(
+    evaluator: &Evaluator,
+    selector: Option<Ciphertext>,
+    c: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Option<Ciphertext> {
+    match selector {
+        Some(s) => Some(and(evaluator, &s, c, ek)),
+        None => Some(c.clone()),
+    }
+}
+
+/// Plays a tournament over `values`. In each match `right_wins(left, right)` must return
+/// encrypted 1 in slots where `right` replaces `left` and 0 otherwise. Returns the winner and,
+/// if `track_index` is set, the one-hot selector vector of the winner.
+///
+/// Ties are always resolved in favour of `left`, thus in favour of the lowest index.
+fn tournament<F>(
+    evaluator: &Evaluator,
+    values: &[Ciphertext],
+    ek: &EvaluationKey,
+    track_index: bool,
+    right_wins: F,
+) -> (Ciphertext, Vec<Ciphertext>)
+where
+    F: Fn(&Ciphertext, &Ciphertext) -> Ciphertext,
+{
+    assert!(!values.is_empty(), "Tournament requires at least one value");
+
+    let mut nodes = values
+        .iter()
+        .map(|v| Node {
+            value: v.clone(),
+            selectors: if track_index { vec![None] } else { vec![] },
+        })
+        .collect::<Vec<Node>>();
+
+    while nodes.len() > 1 {
+        let mut next = Vec::with_capacity((nodes.len() + 1) / 2);
+        let mut iter = nodes.into_iter();
+        while let Some(left) = iter.next() {
+            let right = match iter.next() {
+                Some(right) => right,
+                None => {
+                    // odd one out gets a bye to the next round
+                    next.push(left);
+                    break;
+                }
+            };
+
+            let c = right_wins(&left.value, &right.value);
+
+            let value = select(evaluator, &c, &right.value, &left.value, ek);
+
+            let mut selectors = vec![];
+            if track_index {
+                let not_c = not(evaluator, &c);
+                left.selectors.into_iter().for_each(|s| {
+                    selectors.push(mul_selector(evaluator, s, &not_c, ek));
+                });
+                right.selectors.into_iter().for_each(|s| {
+                    selectors.push(mul_selector(evaluator, s, &c, ek));
+                });
+            }
+
+            next.push(Node { value, selectors });
+        }
+        nodes = next;
+    }
+
+    let winner = nodes.pop().unwrap();
+    let selectors = winner
+        .selectors
+        .into_iter()
+        .map(|s| s.unwrap_or_else(|| one_like(evaluator, &winner.value)))
+        .collect();
+
+    (winner.value, selectors)
+}
+
+/// Returns slot-wise minimum of `values`.
+///
+/// Built as a tournament tree over `univariate_less_than`, hence multiplicative depth grows
+/// with `log2(values.len())` comparisons. All values must lie in `[0, t/2)`.
+pub fn min(
+    evaluator: &Evaluator,
+    values: &[Ciphertext],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    tournament(evaluator, values, ek, false, |l, r| {
+        univariate_less_than(evaluator, r, l, lt, ek)
+    })
+    .0
+}
+
+/// Returns slot-wise maximum of `values`.
+///
+/// See `min` for depth and range requirements.
+pub fn max(
+    evaluator: &Evaluator,
+    values: &[Ciphertext],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    tournament(evaluator, values, ek, false, |l, r| {
+        univariate_less_than(evaluator, l, r, lt, ek)
+    })
+    .0
+}
+
+/// Returns one-hot selector vector of the slot-wise minimum of `values`. `i^th` ciphertext of the
+/// output encrypts 1 in slots where `values[i]` is the minimum and 0 otherwise.
+///
+/// If more than one value equals the minimum, the one with lowest index is selected.
+pub fn argmin(
+    evaluator: &Evaluator,
+    values: &[Ciphertext],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Vec<Ciphertext> {
+    tournament(evaluator, values, ek, true, |l, r| {
+        univariate_less_than(evaluator, r, l, lt, ek)
+    })
+    .1
+}
+
+/// Returns one-hot selector vector of the slot-wise maximum of `values`. `i^th` ciphertext of the
+/// output encrypts 1 in slots where `values[i]` is the maximum and 0 otherwise.
+///
+/// If more than one value equals the maximum, the one with lowest index is selected.
+pub fn argmax(
+    evaluator: &Evaluator,
+    values: &[Ciphertext],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Vec<Ciphertext> {
+    tournament(evaluator, values, ek, true, |l, r| {
+        univariate_less_than(evaluator, l, r, lt, ek)
+    })
+    .1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, Encoding, Modulus, SecretKey};
+    use rand::thread_rng;
+
+    #[test]
+    fn min_max_argmin_argmax_works() {
+        let mut rng = thread_rng();
+
+        // two rounds of comparisons
+        let mut params = BfvParameters::new(&[60; 20], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let modt_by_2 = Modulus::new(params.plaintext_modulus / 2);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let count = 3;
+        let mut m_values = (0..count)
+            .map(|_| modt_by_2.random_vec(evaluator.params().degree, &mut rng))
+            .collect::<Vec<Vec<u64>>>();
+        // ties between first and last value in every other slot
+        for slot in (0..evaluator.params().degree).step_by(2) {
+            m_values[count - 1][slot] = m_values[0][slot];
+        }
+        let values = m_values
+            .iter()
+            .map(|m| {
+                let pt = evaluator.plaintext_encode(m, Encoding::default());
+                evaluator.encrypt(&sk, &pt, &mut rng)
+            })
+            .collect::<Vec<Ciphertext>>();
+
+        let decrypt = |ct: &Ciphertext| -> Vec<u64> {
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default())
+        };
+
+        let res_min = decrypt(&min(&evaluator, &values, &lt, &ek));
+        let res_max = decrypt(&max(&evaluator, &values, &lt, &ek));
+        let res_argmin = argmin(&evaluator, &values, &lt, &ek)
+            .iter()
+            .map(|c| decrypt(c))
+            .collect::<Vec<Vec<u64>>>();
+        let res_argmax = argmax(&evaluator, &values, &lt, &ek)
+            .iter()
+            .map(|c| decrypt(c))
+            .collect::<Vec<Vec<u64>>>();
+
+        for slot in 0..evaluator.params().degree {
+            let column = (0..count).map(|i| m_values[i][slot]).collect::<Vec<u64>>();
+            let expected_min = *column.iter().min().unwrap();
+            let expected_max = *column.iter().max().unwrap();
+            assert_eq!(res_min[slot], expected_min);
+            assert_eq!(res_max[slot], expected_max);
+
+            // lowest index wins ties
+            let index_min = column.iter().position(|v| *v == expected_min).unwrap();
+            let index_max = column.iter().position(|v| *v == expected_max).unwrap();
+            for i in 0..count {
+                assert_eq!(res_argmin[i][slot], (i == index_min) as u64);
+                assert_eq!(res_argmax[i][slot], (i == index_max) as u64);
+            }
+        }
+    }
+}
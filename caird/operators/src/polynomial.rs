@@ -0,0 +1,306 @@
+use crate::{powers_of_x, utils::constant_like};
+use bfv::{Ciphertext, Encoding, EvaluationKey, Evaluator, PolyCache, PolyType, Representation};
+
+/// Removes trailing zero coefficients
+fn trim(coefficients: &[u64]) -> &[u64] {
+    let len = coefficients
+        .iter()
+        .rposition(|c| *c != 0)
+        .map_or(0, |i| i + 1);
+    &coefficients[..len]
+}
+
+/// Returns encryption of `\sum_i coefficients[i] * x^i`. Coefficients must lie in `[0, t)`.
+///
+/// If polynomial is even (resp. odd) it is rewritten as `g(x^2)` (resp. `x * g(x^2)`), which
+/// halves the degree at the cost of a single multiplication. Otherwise it is evaluated with
+/// Paterson-Stockmeyer (see `paterson_stockmeyer`). Zero coefficients are skipped, thus sparse
+/// polynomials only cost plaintext multiplications for their non-zero terms.
+pub fn evaluate_polynomial(
+    evaluator: &Evaluator,
+    x: &Ciphertext,
+    coefficients: &[u64],
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let coefficients = trim(coefficients);
+    if coefficients.len() <= 2 {
+        return evaluate_leaf(evaluator, coefficients, &[], x)
+            .unwrap_or_else(|| constant_like(evaluator, x, 0));
+    }
+
+    let is_even = coefficients.iter().skip(1).step_by(2).all(|c| *c == 0);
+    let is_odd = coefficients.iter().step_by(2).all(|c| *c == 0);
+    if is_even || is_odd {
+        let x_sq = evaluator.relinearize(&evaluator.mul(x, x), ek);
+        if is_even {
+            let g = coefficients
+                .iter()
+                .step_by(2)
+                .copied()
+                .collect::<Vec<u64>>();
+            evaluate_polynomial(evaluator, &x_sq, &g, ek)
+        } else {
+            let g = coefficients
+                .iter()
+                .skip(1)
+                .step_by(2)
+                .copied()
+                .collect::<Vec<u64>>();
+            let g_x_sq = evaluate_polynomial(evaluator, &x_sq, &g, ek);
+            evaluator.relinearize(&evaluator.mul(&g_x_sq, x), ek)
+        }
+    } else {
+        paterson_stockmeyer(evaluator, x, coefficients, ek)
+    }
+}
+
+/// Evaluates polynomial of degree `d` with Paterson-Stockmeyer.
+///
+/// Computes baby steps `x..x^k` for `k ~ sqrt(d/2)` and giant steps `x^k, x^2k, x^4k, ...`.
+/// Polynomial is then recursively divided by the largest giant step as `q * x^(k 2^i) + r`
+/// until remainders have degree less than `k`, which are evaluated with plaintext multiplications
+/// only. This requires about `sqrt(2d) + log2(d)` non-scalar multiplications (each followed by
+/// relinearization) in total.
+fn paterson_stockmeyer(
+    evaluator: &Evaluator,
+    x: &Ciphertext,
+    coefficients: &[u64],
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let degree = coefficients.len() - 1;
+
+    // largest k such that 2k^2 <= degree
+    let mut k = 1;
+    while 2 * (k + 1) * (k + 1) <= degree {
+        k += 1;
+    }
+    let leaves = (coefficients.len() + k - 1) / k;
+    let mut levels = 0;
+    while (1 << levels) < leaves {
+        levels += 1;
+    }
+
+    // x..x^k in Evaluation representation for plaintext multiplications
+    let mut baby_steps = powers_of_x(evaluator, x, k, Representation::Evaluation, ek);
+
+    // x^k, x^2k, ..., x^(k 2^(levels-1))
+    let mut giant_steps: Vec<Ciphertext> = Vec::with_capacity(levels);
+    for i in 0..levels {
+        if i == 0 {
+            let mut x_k = baby_steps[k - 1].clone();
+            evaluator.ciphertext_change_representation(&mut x_k, Representation::Coefficient);
+            giant_steps.push(x_k);
+        } else {
+            let prev = &giant_steps[i - 1];
+            giant_steps.push(evaluator.relinearize(&evaluator.mul(prev, prev), ek));
+        }
+    }
+    baby_steps.truncate(k - 1);
+
+    evaluate_recursive(
+        evaluator,
+        coefficients,
+        &baby_steps,
+        &giant_steps,
+        levels,
+        x,
+        ek,
+    )
+    .unwrap_or_else(|| constant_like(evaluator, x, 0))
+}
+
+/// Evaluates polynomial with at most `k 2^level` `coefficients`, where `k = baby_steps.len() + 1`.
+/// Returns `None` if all coefficients are 0.
+fn evaluate_recursive(
+    evaluator: &Evaluator,
+    coefficients: &[u64],
+    baby_steps: &[Ciphertext],
+    giant_steps: &[Ciphertext],
+    level: usize,
+    like: &Ciphertext,
+    ek: &EvaluationKey,
+) -> Option<Ciphertext> {
+    if level == 0 {
+        return evaluate_leaf(evaluator, coefficients, baby_steps, like);
+    }
+
+    let split = (baby_steps.len() + 1) << (level - 1);
+    if coefficients.len() <= split {
+        return evaluate_recursive(
+            evaluator,
+            coefficients,
+            baby_steps,
+            giant_steps,
+            level - 1,
+            like,
+            ek,
+        );
+    }
+
+    let r = evaluate_recursive(
+        evaluator,
+        &coefficients[..split],
+        baby_steps,
+        giant_steps,
+        level - 1,
+        like,
+        ek,
+    );
+    // q is a constant, so multiplication by giant step is scalar
+    let q_coefficients = trim(&coefficients[split..]);
+    if q_coefficients.len() == 1 {
+        let mut res = mul_scalar(evaluator, &giant_steps[level - 1], q_coefficients[0]);
+        evaluator.ciphertext_change_representation(&mut res, Representation::Coefficient);
+        if let Some(r) = r {
+            evaluator.add_assign(&mut res, &r);
+        }
+        return Some(res);
+    }
+
+    let q = evaluate_recursive(
+        evaluator,
+        q_coefficients,
+        baby_steps,
+        giant_steps,
+        level - 1,
+        like,
+        ek,
+    );
+    match q {
+        Some(q) => {
+            // q * x^(k 2^(level-1)) + r
+            let mut res = evaluator.relinearize(&evaluator.mul(&q, &giant_steps[level - 1]), ek);
+            if let Some(r) = r {
+                evaluator.add_assign(&mut res, &r);
+            }
+            Some(res)
+        }
+        None => r,
+    }
+}
+
+/// Returns `ct * alpha` in `Evaluation` representation
+fn mul_scalar(evaluator: &Evaluator, ct: &Ciphertext, alpha: u64) -> Ciphertext {
+    let pt_alpha = evaluator.plaintext_encode(
+        &vec![alpha; evaluator.params().degree],
+        Encoding::simd(ct.level(), PolyCache::Mul(PolyType::Q)),
+    );
+    let mut ct = ct.clone();
+    evaluator.ciphertext_change_representation(&mut ct, Representation::Evaluation);
+    evaluator.mul_poly_assign(&mut ct, pt_alpha.mul_poly_ref());
+    ct
+}
+
+/// Evaluates `\sum_j coefficients[j] * x^j` using only plaintext multiplications, where
+/// `baby_steps` contains `x..x^(coefficients.len() - 1)` in `Evaluation` representation
+/// and `like` is `x` in `Coefficient` representation. Returns `None` if all coefficients are 0.
+///
+/// Output is in `Coefficient` representation.
+fn evaluate_leaf(
+    evaluator: &Evaluator,
+    coefficients: &[u64],
+    baby_steps: &[Ciphertext],
+    like: &Ciphertext,
+) -> Option<Ciphertext> {
+    let level = like.level();
+    let degree = evaluator.params().degree;
+
+    let mut sum: Option<Ciphertext> = None;
+    for (j, alpha) in coefficients.iter().enumerate().skip(1) {
+        if *alpha == 0 {
+            continue;
+        }
+        let product = if j == 1 && baby_steps.is_empty() {
+            // degree 1 polynomial without baby steps
+            mul_scalar(evaluator, like, *alpha)
+        } else {
+            mul_scalar(evaluator, &baby_steps[j - 1], *alpha)
+        };
+        match sum.as_mut() {
+            Some(s) => evaluator.add_assign(s, &product),
+            None => sum = Some(product),
+        }
+    }
+
+    let mut sum = match (sum, coefficients.first()) {
+        (Some(mut s), Some(c0)) if *c0 != 0 => {
+            let pt = evaluator.plaintext_encode(
+                &vec![*c0; degree],
+                Encoding::simd(level, PolyCache::AddSub(Representation::Evaluation)),
+            );
+            evaluator.add_assign_plaintext(&mut s, &pt);
+            s
+        }
+        (Some(s), _) => s,
+        (None, Some(c0)) if *c0 != 0 => constant_like(evaluator, like, *c0),
+        (None, _) => return None,
+    };
+
+    evaluator.ciphertext_change_representation(&mut sum, Representation::Coefficient);
+    Some(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, SecretKey};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn evaluate_polynomial_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 10], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let modt = &evaluator.params().plaintext_modulus_op;
+        let m = modt.random_vec(evaluator.params().degree, &mut rng);
+        let x = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&m, Encoding::default()),
+            &mut rng,
+        );
+
+        // dense, sparse and odd polynomials
+        let dense = modt.random_vec(30, &mut rng);
+        let sparse = (0..40)
+            .map(|i| {
+                if i % 7 == 0 {
+                    rng.gen_range(0..modt.modulus())
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<u64>>();
+        let odd = (0..20)
+            .map(|i| {
+                if i & 1 == 1 {
+                    rng.gen_range(0..modt.modulus())
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<u64>>();
+
+        for coefficients in [dense, sparse, odd] {
+            let res_ct = evaluate_polynomial(&evaluator, &x, &coefficients, &ek);
+            let res_m =
+                evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+
+            // Horner's rule
+            let expected = m
+                .iter()
+                .map(|a| {
+                    coefficients.iter().rev().fold(0, |acc, c| {
+                        modt.add_mod_fast(modt.mul_mod_fast(acc, *a), *c)
+                    })
+                })
+                .collect::<Vec<u64>>();
+            assert_eq!(res_m, expected);
+        }
+    }
+}
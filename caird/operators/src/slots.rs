@@ -0,0 +1,132 @@
+use crate::utils::mul_plain;
+use bfv::{Ciphertext, EvaluationKey, Evaluator};
+
+/// Returns rotation indices required by `sum_slots`, `inner_product` and `broadcast_slot`.
+///
+/// Slots are arranged as a `2 x (degree/2)` matrix. Rows are summed with rotations by
+/// `1, 2, 4, ..., degree/4` followed by row swap, which has index `2 * degree - 1`.
+/// Galois keys for these indices must be present in `EvaluationKey` at the level of the input
+/// ciphertext.
+pub fn sum_slots_rotation_indices(degree: usize) -> Vec<isize> {
+    assert!(degree.is_power_of_two() && degree >= 4);
+
+    let mut indices = vec![];
+    let mut step = 1;
+    while step < degree / 2 {
+        indices.push(step as isize);
+        step <<= 1;
+    }
+    indices.push((2 * degree - 1) as isize);
+    indices
+}
+
+/// Returns ciphertext that encrypts sum of all slots of `ct` in every slot.
+///
+/// Costs `log2(degree)` rotations (see `sum_slots_rotation_indices`).
+pub fn sum_slots(evaluator: &Evaluator, ct: &Ciphertext, ek: &EvaluationKey) -> Ciphertext {
+    let degree = evaluator.params().degree;
+
+    let mut sum = ct.clone();
+    let mut step = 1;
+    while step < degree / 2 {
+        let rotated = evaluator.rotate(&sum, step as isize, ek);
+        evaluator.add_assign(&mut sum, &rotated);
+        step <<= 1;
+    }
+
+    // each row now holds sum of the row in every slot
+    let swapped = evaluator.rotate(&sum, (2 * degree - 1) as isize, ek);
+    evaluator.add_assign(&mut sum, &swapped);
+    sum
+}
+
+/// Returns ciphertext that encrypts `\sum_i weights[i] * m[i]` in every slot, where `m` is the
+/// message encrypted by `ct`. Missing weights are treated as 0.
+///
+/// Requires same rotation keys as `sum_slots`.
+pub fn inner_product(
+    evaluator: &Evaluator,
+    ct: &Ciphertext,
+    weights: &[u64],
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    assert!(weights.len() <= evaluator.params().degree);
+    let weighted = mul_plain(evaluator, ct, weights);
+    sum_slots(evaluator, &weighted, ek)
+}
+
+/// Returns ciphertext that encrypts value of `i^th` slot of `ct` in every slot.
+///
+/// Masks all other slots and sums slots, thus requires same rotation keys as `sum_slots`.
+pub fn broadcast_slot(
+    evaluator: &Evaluator,
+    ct: &Ciphertext,
+    i: usize,
+    ek: &EvaluationKey,
+) -> Ciphertext {
+    let degree = evaluator.params().degree;
+    assert!(i < degree);
+
+    let mut mask = vec![0u64; degree];
+    mask[i] = 1;
+    inner_product(evaluator, ct, &mask, ek)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, Encoding, SecretKey};
+    use rand::thread_rng;
+
+    #[test]
+    fn slot_operators_work() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 5], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let rtg_indices = sum_slots_rotation_indices(params.degree);
+        let ek = EvaluationKey::new(
+            &params,
+            &sk,
+            &[],
+            &vec![0; rtg_indices.len()],
+            &rtg_indices,
+            &mut rng,
+        );
+        let evaluator = Evaluator::new(params);
+
+        let degree = evaluator.params().degree;
+        let modt = &evaluator.params().plaintext_modulus_op;
+        let m = modt.random_vec(degree, &mut rng);
+        let weights = modt.random_vec(degree, &mut rng);
+        let ct = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&m, Encoding::default()),
+            &mut rng,
+        );
+
+        let decrypt = |ct: &Ciphertext| -> Vec<u64> {
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default())
+        };
+
+        let sum = m.iter().fold(0, |acc, v| modt.add_mod_fast(acc, *v));
+        assert_eq!(decrypt(&sum_slots(&evaluator, &ct, &ek)), vec![sum; degree]);
+
+        let dot = m.iter().zip(weights.iter()).fold(0, |acc, (v, w)| {
+            modt.add_mod_fast(acc, modt.mul_mod_fast(*v, *w))
+        });
+        assert_eq!(
+            decrypt(&inner_product(&evaluator, &ct, &weights, &ek)),
+            vec![dot; degree]
+        );
+
+        for i in [0, 3, degree / 2, degree - 1] {
+            assert_eq!(
+                decrypt(&broadcast_slot(&evaluator, &ct, i, &ek)),
+                vec![m[i]; degree]
+            );
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    and, select, univariate_less_than,
+    utils::{constant_like, one_minus},
+    LessThanCoefficients,
+};
+use bfv::{Ciphertext, EvaluationKey, Evaluator};
+
+/// Returns comparators of Batcher's merge exchange network (Knuth, TAOCP Vol. 3, Algorithm 5.2.2M)
+/// for `n` inputs, grouped in layers.
+///
+/// Comparators within a layer touch disjoint indices, thus can be evaluated independently. Network
+/// has `k(k+1)/2` layers where `k = ceil(log2(n))`. Each comparator `(i, j)` has `i < j`.
+pub fn sorting_network(n: usize) -> Vec<Vec<(usize, usize)>> {
+    let mut layers = vec![];
+    if n < 2 {
+        return layers;
+    }
+
+    let t = (usize::BITS - (n - 1).leading_zeros()) as usize;
+    let mut p = 1 << (t - 1);
+    while p > 0 {
+        let mut q = 1 << (t - 1);
+        let mut r = 0;
+        let mut d = p;
+        loop {
+            let layer = (0..(n - d))
+                .filter(|i| i & p == r)
+                .map(|i| (i, i + d))
+                .collect::<Vec<(usize, usize)>>();
+            if !layer.is_empty() {
+                layers.push(layer);
+            }
+
+            if q == p {
+                break;
+            }
+            d = q - p;
+            q >>= 1;
+            r = p;
+        }
+        p >>= 1;
+    }
+
+    layers
+}
+
+/// Returns `(b, a)` in slots where `swap` is 1 and `(a, b)` where it is 0.
+///
+/// Costs a single multiplication, since second output is `a + b` minus the first.
+fn conditional_swap(
+    evaluator: &Evaluator,
+    swap: &Ciphertext,
+    a: &Ciphertext,
+    b: &Ciphertext,
+    ek: &EvaluationKey,
+) -> (Ciphertext, Ciphertext) {
+    let first = select(evaluator, swap, b, a, ek);
+    let mut second = evaluator.add(a, b);
+    evaluator.sub_assign(&mut second, &first);
+    (first, second)
+}
+
+/// Returns `(max(a, b), min(a, b))`.
+///
+/// Costs a single comparison and a single multiplication.
+pub fn compare_and_swap(
+    evaluator: &Evaluator,
+    a: &Ciphertext,
+    b: &Ciphertext,
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> (Ciphertext, Ciphertext) {
+    let a_lt_b = univariate_less_than(evaluator, a, b, lt, ek);
+    conditional_swap(evaluator, &a_lt_b, a, b, ek)
+}
+
+/// Sorts `values` slot-wise in descending order, ie `i^th` output ciphertext encrypts `i^th`
+/// largest value of each slot.
+///
+/// Evaluates `sorting_network` with `compare_and_swap`, thus multiplicative depth is
+/// `O(log^2(values.len()))` comparisons. All values must lie in `[0, t/2)`.
+pub fn sort(
+    evaluator: &Evaluator,
+    values: &[Ciphertext],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> Vec<Ciphertext> {
+    let mut values = values.to_vec();
+    for layer in sorting_network(values.len()) {
+        for (i, j) in layer {
+            let (hi, lo) = compare_and_swap(evaluator, &values[i], &values[j], lt, ek);
+            values[i] = hi;
+            values[j] = lo;
+        }
+    }
+    values
+}
+
+/// Sorts `keys` slot-wise in descending order and moves `payloads` along with their keys.
+/// Returns sorted keys and sorted payloads.
+///
+/// `payloads[i]` holds ciphertexts associated with `keys[i]` (for ex, quantity and order id) and
+/// all entries of `payloads` must have the same length. Sort is stable, ie elements with equal
+/// keys retain their relative order. This is achieved by carrying original index of each element
+/// through the network and swapping at comparator `(i, j)` only if `key_i < key_j` or
+/// `key_i == key_j` and `index_j < index_i`.
+///
+/// Each comparator costs two comparisons of keys (evaluated independently), a comparison of
+/// indices and two multiplications before the swap. Keys must lie in `[0, t/2)`.
+pub fn sort_by_key(
+    evaluator: &Evaluator,
+    keys: &[Ciphertext],
+    payloads: &[Vec<Ciphertext>],
+    lt: &LessThanCoefficients,
+    ek: &EvaluationKey,
+) -> (Vec<Ciphertext>, Vec<Vec<Ciphertext>>) {
+    assert!(keys.len() == payloads.len());
+    if let Some(first) = payloads.first() {
+        assert!(payloads.iter().all(|p| p.len() == first.len()));
+    }
+
+    let mut keys = keys.to_vec();
+    let mut payloads = payloads.to_vec();
+    let mut indices = keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| constant_like(evaluator, k, i as u64))
+        .collect::<Vec<Ciphertext>>();
+
+    for layer in sorting_network(keys.len()) {
+        for (i, j) in layer {
+            let lt_ij = univariate_less_than(evaluator, &keys[i], &keys[j], lt, ek);
+            let gt_ij = univariate_less_than(evaluator, &keys[j], &keys[i], lt, ek);
+
+            // eq = 1 - lt_ij - gt_ij
+            let mut eq = one_minus(evaluator, &lt_ij);
+            evaluator.sub_assign(&mut eq, &gt_ij);
+
+            // tie-break on original index
+            let index_gt = univariate_less_than(evaluator, &indices[j], &indices[i], lt, ek);
+            let mut swap = and(evaluator, &eq, &index_gt, ek);
+            evaluator.add_assign(&mut swap, &lt_ij);
+
+            let (a, b) = conditional_swap(evaluator, &swap, &keys[i], &keys[j], ek);
+            keys[i] = a;
+            keys[j] = b;
+
+            let (a, b) = conditional_swap(evaluator, &swap, &indices[i], &indices[j], ek);
+            indices[i] = a;
+            indices[j] = b;
+
+            for k in 0..payloads[i].len() {
+                let (a, b) =
+                    conditional_swap(evaluator, &swap, &payloads[i][k], &payloads[j][k], ek);
+                payloads[i][k] = a;
+                payloads[j][k] = b;
+            }
+        }
+    }
+
+    (keys, payloads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{BfvParameters, Encoding, Modulus, SecretKey};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn sorting_network_sorts_all_binary_inputs() {
+        // zero-one principle: a comparator network sorts all inputs iff it sorts all 0/1 inputs
+        for n in 0..=12 {
+            let network = sorting_network(n);
+            for bits in 0..(1u32 << n) {
+                let mut values = (0..n).map(|i| (bits >> i) & 1).collect::<Vec<u32>>();
+                network.iter().flatten().for_each(|&(i, j)| {
+                    if values[i] < values[j] {
+                        values.swap(i, j);
+                    }
+                });
+                assert!(values.windows(2).all(|w| w[0] >= w[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn sort_works() {
+        let mut rng = thread_rng();
+
+        // 3 layers of comparisons for 4 values
+        let mut params = BfvParameters::new(&[60; 30], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let modt_by_2 = Modulus::new(params.plaintext_modulus / 2);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let count = 4;
+        let m_values = (0..count)
+            .map(|_| modt_by_2.random_vec(evaluator.params().degree, &mut rng))
+            .collect::<Vec<Vec<u64>>>();
+        let values = m_values
+            .iter()
+            .map(|m| {
+                let pt = evaluator.plaintext_encode(m, Encoding::default());
+                evaluator.encrypt(&sk, &pt, &mut rng)
+            })
+            .collect::<Vec<Ciphertext>>();
+
+        let m_sorted = sort(&evaluator, &values, &lt, &ek)
+            .iter()
+            .map(|c| evaluator.plaintext_decode(&evaluator.decrypt(&sk, c), Encoding::default()))
+            .collect::<Vec<Vec<u64>>>();
+
+        for slot in 0..evaluator.params().degree {
+            let mut expected = (0..count).map(|i| m_values[i][slot]).collect::<Vec<u64>>();
+            expected.sort_by(|a, b| b.cmp(a));
+            let res = (0..count).map(|i| m_sorted[i][slot]).collect::<Vec<u64>>();
+            assert_eq!(res, expected);
+        }
+    }
+
+    #[test]
+    fn sort_by_key_works() {
+        let mut rng = thread_rng();
+
+        // 3 layers of comparisons for 3 values
+        let mut params = BfvParameters::new(&[60; 35], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let modt = Modulus::new(params.plaintext_modulus);
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let degree = evaluator.params().degree;
+        let count = 3;
+        // keys from a tiny range so that most slots have ties
+        let m_keys = (0..count)
+            .map(|_| {
+                (0..degree)
+                    .map(|_| rng.gen_range(0..2u64))
+                    .collect::<Vec<u64>>()
+            })
+            .collect::<Vec<Vec<u64>>>();
+        let m_payloads = (0..count)
+            .map(|_| modt.random_vec(degree, &mut rng))
+            .collect::<Vec<Vec<u64>>>();
+
+        let mut encrypt = |m: &[u64]| {
+            let pt = evaluator.plaintext_encode(m, Encoding::default());
+            evaluator.encrypt(&sk, &pt, &mut rng)
+        };
+        let keys = m_keys
+            .iter()
+            .map(|m| encrypt(m))
+            .collect::<Vec<Ciphertext>>();
+        let payloads = m_payloads
+            .iter()
+            .map(|m| vec![encrypt(m)])
+            .collect::<Vec<Vec<Ciphertext>>>();
+
+        let (sorted_keys, sorted_payloads) = sort_by_key(&evaluator, &keys, &payloads, &lt, &ek);
+
+        let decrypt = |ct: &Ciphertext| -> Vec<u64> {
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default())
+        };
+        let res_keys = sorted_keys.iter().map(decrypt).collect::<Vec<Vec<u64>>>();
+        let res_payloads = sorted_payloads
+            .iter()
+            .map(|p| decrypt(&p[0]))
+            .collect::<Vec<Vec<u64>>>();
+
+        for slot in 0..degree {
+            // stable sort of indices by descending key
+            let mut order = (0..count).collect::<Vec<usize>>();
+            order.sort_by(|a, b| m_keys[*b][slot].cmp(&m_keys[*a][slot]));
+
+            for (i, index) in order.into_iter().enumerate() {
+                assert_eq!(res_keys[i][slot], m_keys[index][slot]);
+                assert_eq!(res_payloads[i][slot], m_payloads[index][slot]);
+            }
+        }
+    }
+}
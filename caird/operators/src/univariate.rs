@@ -0,0 +1,246 @@
+use crate::evaluate_polynomial;
+use bfv::{BfvParameters, Ciphertext, EvaluationKey, Evaluator, Modulus};
+
+/// Returns prime factors of `n` in ascending order, with multiplicity
+fn factorize(mut n: u64) -> Vec<u64> {
+    let mut factors = vec![];
+    let mut p = 2;
+    while p * p <= n {
+        while n % p == 0 {
+            factors.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Returns smallest generator of multiplicative group of `Z_t`. `t` must be prime.
+fn generator(modt: &Modulus) -> u64 {
+    let order = modt.modulus() - 1;
+    let mut factors = factorize(order);
+    factors.dedup();
+    (2..modt.modulus())
+        .find(|g| {
+            factors
+                .iter()
+                .all(|q| modt.exp(*g, (order / q) as usize) != 1)
+        })
+        .unwrap_or(1)
+}
+
+/// Returns `X_i = \sum_k a_k w^{ik}` for every `i` in `0..a.len()`, where `w` has multiplicative
+/// order `a.len()` and `factors` are prime factors of `a.len()`.
+///
+/// Mixed radix Cooley-Tukey: splits `a` into `p` decimated sequences, where `p` is the first
+/// factor, and combines their transforms in `O(p * a.len())`. Thus total cost is
+/// `O(a.len() * \sum factors)`, which is `O(n log n)` when `n` is a power of 2.
+fn dft(a: &[u64], w: u64, factors: &[u64], modt: &Modulus) -> Vec<u64> {
+    let n = a.len();
+    if n == 1 {
+        return a.to_vec();
+    }
+
+    let p = factors[0] as usize;
+    let m = n / p;
+    let w_p = modt.exp(w, p);
+    let sub_transforms = (0..p)
+        .map(|r| {
+            let sub = a.iter().skip(r).step_by(p).copied().collect::<Vec<u64>>();
+            dft(&sub, w_p, &factors[1..], modt)
+        })
+        .collect::<Vec<Vec<u64>>>();
+
+    // X_i = \sum_r w^{ir} A_r[i mod m]
+    let mut res = vec![0u64; n];
+    let mut w_i = 1;
+    for i in 0..n {
+        let mut w_ir = 1;
+        let mut sum = 0;
+        for sub in sub_transforms.iter() {
+            sum = modt.add_mod_fast(sum, modt.mul_mod_fast(sub[i % m], w_ir));
+            w_ir = modt.mul_mod_fast(w_ir, w_i);
+        }
+        res[i] = sum;
+        w_i = modt.mul_mod_fast(w_i, w);
+    }
+    res
+}
+
+/// Returns `S_i = \sum_{a=1}^{t-1} values[a] * a^{-i}` for every `i` in `0..t-1`. `values[0]` is
+/// ignored.
+///
+/// Substituting `a = g^k` for generator `g` turns `S` into a discrete Fourier transform of length
+/// `t-1` with root `g^{-1}`, which is evaluated with `dft`. For NTT friendly plaintext moduli `t-1`
+/// is highly composite and this takes `O(t log t)` multiplications.
+pub fn inverse_power_sums(values: &[u64], modt: &Modulus) -> Vec<u64> {
+    let t = modt.modulus();
+    assert!(values.len() as u64 == t, "Expected {t} values");
+
+    let g = generator(modt);
+    // u_k = values[g^k]
+    let mut u = Vec::with_capacity((t - 1) as usize);
+    let mut g_k = 1;
+    for _ in 0..(t - 1) {
+        u.push(values[g_k as usize]);
+        g_k = modt.mul_mod_fast(g_k, g);
+    }
+
+    dft(&u, modt.inv(g), &factorize(t - 1), modt)
+}
+
+/// Returns coefficients `c_0, ..., c_{t-1}` of the unique polynomial `f` of degree at most `t-1`
+/// over `Z_t` such that `f(a) = values[a]` for every `a` in `Z_t`.
+///
+/// Uses `c_0 = f(0)`, `c_i = -\sum_{a=1}^{t-1} f(a) a^{-i}` for `0 < i < t-1` and
+/// `c_{t-1} = -\sum_{a=0}^{t-1} f(a)`. Middle coefficients are computed with
+/// `inverse_power_sums`.
+pub fn interpolate(values: &[u64], modt: &Modulus) -> Vec<u64> {
+    let t = modt.modulus() as usize;
+    assert!(values.len() == t, "Expected {t} values");
+    values.iter().for_each(|v| assert!(*v < t as u64));
+
+    let mut coefficients = inverse_power_sums(values, modt);
+    modt.neg_mod_fast_vec(&mut coefficients);
+    coefficients[0] = values[0];
+
+    let sum = values.iter().fold(0, |acc, v| modt.add_mod_fast(acc, *v));
+    coefficients.push(modt.neg_mod_fast(sum));
+
+    coefficients
+}
+
+/// Arbitrary function `f: Z_t -> Z_t` represented by its interpolation polynomial.
+///
+/// Any table lookup on encrypted values (for ex, rounding to tick size, fee brackets, clamping)
+/// can be expressed as a `LookupTable` and evaluated on a ciphertext with `evaluate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LookupTable {
+    coefficients: Vec<u64>,
+}
+
+impl LookupTable {
+    /// Returns lookup table with `f(a) = table[a]`. `table` must have exactly `t` values in
+    /// `[0, t)`.
+    pub fn from_table(params: &BfvParameters, table: &[u64]) -> LookupTable {
+        LookupTable {
+            coefficients: interpolate(table, &params.plaintext_modulus_op),
+        }
+    }
+
+    /// Returns lookup table of function `f`. `f` is evaluated for every `a` in `Z_t` and must
+    /// return values in `[0, t)`.
+    pub fn from_fn<F: Fn(u64) -> u64>(params: &BfvParameters, f: F) -> LookupTable {
+        let table = (0..params.plaintext_modulus).map(f).collect::<Vec<u64>>();
+        LookupTable::from_table(params, &table)
+    }
+
+    pub fn coefficients(&self) -> &[u64] {
+        &self.coefficients
+    }
+
+    /// Returns true if `f(-x) = f(x)`, ie all odd coefficients are 0
+    pub fn is_even(&self) -> bool {
+        self.coefficients.iter().skip(1).step_by(2).all(|c| *c == 0)
+    }
+
+    /// Returns true if `f(-x) = -f(x)`, ie all even coefficients are 0
+    pub fn is_odd(&self) -> bool {
+        self.coefficients.iter().step_by(2).all(|c| *c == 0)
+    }
+
+    /// Returns encryption of `f(x)`.
+    ///
+    /// Interpolation polynomial is evaluated with `evaluate_polynomial`, thus even (resp. odd)
+    /// functions only cost evaluation of a polynomial of half the degree in `x^2`.
+    pub fn evaluate(
+        &self,
+        evaluator: &Evaluator,
+        x: &Ciphertext,
+        ek: &EvaluationKey,
+    ) -> Ciphertext {
+        evaluate_polynomial(evaluator, x, &self.coefficients, ek)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::{Encoding, SecretKey};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn interpolate_works() {
+        let mut rng = thread_rng();
+
+        // 65537 - 1 = 2^16, 97 - 1 = 2^5 * 3 and 103 - 1 = 2 * 3 * 17
+        for t in [97u64, 103, 65537] {
+            let modt = Modulus::new(t);
+            let values = modt.random_vec(t as usize, &mut rng);
+            let coefficients = interpolate(&values, &modt);
+            assert_eq!(coefficients.len(), t as usize);
+
+            // check a few points with Horner's rule
+            for _ in 0..10 {
+                let a = rng.gen_range(0..t);
+                let eval = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, c| modt.add_mod_fast(modt.mul_mod_fast(acc, a), *c));
+                assert_eq!(eval, values[a as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_table_works() {
+        let mut rng = thread_rng();
+
+        // 97 = 1 mod 32
+        let mut params = BfvParameters::new(&[60; 10], 97, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let t = params.plaintext_modulus;
+        let random_table = (0..t).map(|_| rng.gen_range(0..t)).collect::<Vec<u64>>();
+        let tables = vec![
+            // arbitrary
+            LookupTable::from_table(&params, &random_table),
+            // even
+            LookupTable::from_fn(&params, |a| std::cmp::min(a, t - a) / 10),
+            // odd
+            LookupTable::from_fn(&params, |a| (a * a * a) % t),
+        ];
+        assert!(tables[1].is_even());
+        assert!(tables[2].is_odd());
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+
+        let m = evaluator
+            .params()
+            .plaintext_modulus_op
+            .random_vec(evaluator.params().degree, &mut rng);
+        let x = evaluator.encrypt(
+            &sk,
+            &evaluator.plaintext_encode(&m, Encoding::default()),
+            &mut rng,
+        );
+
+        let functions: Vec<Box<dyn Fn(u64) -> u64>> = vec![
+            Box::new(|a| random_table[a as usize]),
+            Box::new(|a| std::cmp::min(a, t - a) / 10),
+            Box::new(|a| (a * a * a) % t),
+        ];
+        for (table, f) in tables.iter().zip(functions.iter()) {
+            let res_ct = table.evaluate(&evaluator, &x, &ek);
+            let res_m =
+                evaluator.plaintext_decode(&evaluator.decrypt(&sk, &res_ct), Encoding::default());
+            let expected = m.iter().map(|a| f(*a)).collect::<Vec<u64>>();
+            assert_eq!(res_m, expected);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use bfv::{Ciphertext, Encoding, Evaluator, SecretKey};
+use bfv::{Ciphertext, Encoding, Evaluator, PolyCache, PolyType, Representation, SecretKey};
 use byteorder::{ByteOrder, LittleEndian};
 use std::{
     fmt::format,
@@ -37,6 +37,51 @@ pub fn decrypt_and_print(evaluator: &Evaluator, ct: &Ciphertext, sk: &SecretKey,
     println!("{tag} m: {:?}", m);
 }
 
+/// Returns encrypted `value` in every slot with the same level and representation as `ct`.
+///
+/// The result is a trivial encryption (c1 = 0), thus carries no noise.
+pub fn constant_like(evaluator: &Evaluator, ct: &Ciphertext, value: u64) -> Ciphertext {
+    let mut res = evaluator.sub(ct, ct);
+    let pt = evaluator.plaintext_encode(
+        &vec![value; evaluator.params().degree],
+        Encoding::simd(
+            ct.level(),
+            PolyCache::AddSub(ct.c_ref()[0].representation().clone()),
+        ),
+    );
+    evaluator.add_assign_plaintext(&mut res, &pt);
+    res
+}
+
+/// Returns encrypted 1 in every slot with the same level and representation as `ct`.
+pub fn one_like(evaluator: &Evaluator, ct: &Ciphertext) -> Ciphertext {
+    constant_like(evaluator, ct, 1)
+}
+
+/// Returns `ct * m` for plaintext `m`. Output is in `Coefficient` representation.
+pub fn mul_plain(evaluator: &Evaluator, ct: &Ciphertext, m: &[u64]) -> Ciphertext {
+    let pt = evaluator.plaintext_encode(m, Encoding::simd(ct.level(), PolyCache::Mul(PolyType::Q)));
+    let mut res = ct.clone();
+    evaluator.ciphertext_change_representation(&mut res, Representation::Evaluation);
+    evaluator.mul_plaintext_assign(&mut res, &pt);
+    evaluator.ciphertext_change_representation(&mut res, Representation::Coefficient);
+    res
+}
+
+/// Returns `1 - c`
+pub fn one_minus(evaluator: &Evaluator, c: &Ciphertext) -> Ciphertext {
+    let pt = evaluator.plaintext_encode(
+        &vec![1; evaluator.params().degree],
+        Encoding::simd(
+            c.level(),
+            PolyCache::AddSub(c.c_ref()[0].representation().clone()),
+        ),
+    );
+    let mut res = evaluator.negate(c);
+    evaluator.add_assign_plaintext(&mut res, &pt);
+    res
+}
+
 pub fn convert_u64_to_i64(values: &[u64], modq: u64) -> Vec<i64> {
     let q_by_2 = modq / 2;
 
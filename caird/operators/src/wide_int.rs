@@ -0,0 +1,299 @@
+use crate::{
+    equal, less_than_plain, univariate_less_than,
+    utils::{mul_plain, one_minus},
+    LessThanCoefficients,
+};
+use bfv::{Ciphertext, Encoding, EvaluationKey, Evaluator, SecretKey};
+use rand::{CryptoRng, RngCore};
+
+/// Unsigned integer decomposed in radix `2^limb_bits` over several ciphertexts.
+///
+/// `i^th` ciphertext of `limbs` encrypts `i^th` least significant limb of the integer. Slots of the ciphertexts
+/// are independent, thus a single `WideInt` holds as many integers as there are slots.
+///
+/// Each limb is always kept in `[0, 2^limb_bits)`. `limb_bits` is chosen such that sum of two limbs plus a carry
+/// stays below `t/2`, which is the range in which `univariate_less_than` is correct.
+#[derive(Clone, Debug)]
+pub struct WideInt {
+    limbs: Vec<Ciphertext>,
+    limb_bits: usize,
+}
+
+impl WideInt {
+    pub fn new(limbs: Vec<Ciphertext>, limb_bits: usize) -> WideInt {
+        assert!(!limbs.is_empty());
+        WideInt { limbs, limb_bits }
+    }
+
+    /// Returns largest `limb_bits` supported by plaintext modulus `t`, ie largest `b` such that
+    /// `2 * (2^b - 1) + 1 < t/2`
+    pub fn max_limb_bits(t: u64) -> usize {
+        let t_by_2 = t / 2;
+        assert!(t_by_2 >= 4);
+        (63 - t_by_2.leading_zeros()) as usize - 1
+    }
+
+    /// Returns no. of limbs required to hold a `bits` bit integer with `limb_bits` bits per limb
+    pub fn limbs_for_bits(bits: usize, limb_bits: usize) -> usize {
+        (bits + limb_bits - 1) / limb_bits
+    }
+
+    pub fn limbs(&self) -> &[Ciphertext] {
+        &self.limbs
+    }
+
+    pub fn limb_bits(&self) -> usize {
+        self.limb_bits
+    }
+
+    /// Decomposes `values` into `limb_count` limbs of `max_limb_bits` bits and encrypts each limb.
+    ///
+    /// Panics if any value does not fit in `limb_count` limbs
+    pub fn encrypt<R: CryptoRng + RngCore>(
+        evaluator: &Evaluator,
+        sk: &SecretKey,
+        values: &[u64],
+        limb_count: usize,
+        rng: &mut R,
+    ) -> WideInt {
+        let limb_bits = WideInt::max_limb_bits(evaluator.params().plaintext_modulus);
+        let total_bits = limb_bits * limb_count;
+        if total_bits < 64 {
+            values.iter().for_each(|v| {
+                assert!(
+                    *v < (1u64 << total_bits),
+                    "{v} does not fit in {total_bits} bits"
+                );
+            });
+        }
+
+        let mask = (1u64 << limb_bits) - 1;
+        let limbs = (0..limb_count)
+            .map(|i| {
+                let m = values
+                    .iter()
+                    .map(|v| {
+                        let shift = i * limb_bits;
+                        if shift >= 64 {
+                            0
+                        } else {
+                            (*v >> shift) & mask
+                        }
+                    })
+                    .collect::<Vec<u64>>();
+                let pt = evaluator.plaintext_encode(&m, Encoding::default());
+                evaluator.encrypt(sk, &pt, rng)
+            })
+            .collect();
+
+        WideInt { limbs, limb_bits }
+    }
+
+    /// Decrypts and recomposes limbs. Returns a value for each slot.
+    pub fn decrypt(&self, evaluator: &Evaluator, sk: &SecretKey) -> Vec<u128> {
+        assert!(self.limb_bits * self.limbs.len() <= 128);
+
+        let mut values = vec![0u128; evaluator.params().degree];
+        self.limbs.iter().enumerate().for_each(|(i, limb)| {
+            let m = evaluator.plaintext_decode(&evaluator.decrypt(sk, limb), Encoding::default());
+            values.iter_mut().zip(m.iter()).for_each(|(v, l)| {
+                *v += (*l as u128) << (i * self.limb_bits);
+            });
+        });
+        values
+    }
+
+    /// Returns encrypted 1 in slots where `self < other` and 0 otherwise.
+    ///
+    /// Limbs are compared lexicographically starting at the least significant one:
+    /// `lt = lt_i + eq_i * lt`, where `lt_i` and `eq_i` are less than and equality of `i^th` limbs.
+    /// Comparisons of limbs are independent, thus depth only grows by one multiplication per limb.
+    pub fn less_than(
+        &self,
+        evaluator: &Evaluator,
+        other: &WideInt,
+        lt: &LessThanCoefficients,
+        ek: &EvaluationKey,
+    ) -> Ciphertext {
+        self.assert_compatible(other);
+
+        let mut res = univariate_less_than(evaluator, &self.limbs[0], &other.limbs[0], lt, ek);
+        for i in 1..self.limbs.len() {
+            let lt_i = univariate_less_than(evaluator, &self.limbs[i], &other.limbs[i], lt, ek);
+            let eq_i = equal(evaluator, &self.limbs[i], &other.limbs[i], ek);
+
+            let mut tmp = evaluator.relinearize(&evaluator.mul(&eq_i, &res), ek);
+            evaluator.add_assign(&mut tmp, &lt_i);
+            res = tmp;
+        }
+        res
+    }
+
+    /// Returns encrypted 1 in slots where `self == other` and 0 otherwise.
+    ///
+    /// Computed as product of equality of limbs.
+    pub fn equal(&self, evaluator: &Evaluator, other: &WideInt, ek: &EvaluationKey) -> Ciphertext {
+        self.assert_compatible(other);
+
+        let mut eqs = self
+            .limbs
+            .iter()
+            .zip(other.limbs.iter())
+            .map(|(a, b)| equal(evaluator, a, b, ek))
+            .collect::<Vec<Ciphertext>>();
+
+        // multiply in a tree to minimise depth
+        while eqs.len() > 1 {
+            let mut next = Vec::with_capacity((eqs.len() + 1) / 2);
+            for pair in eqs.chunks(2) {
+                if pair.len() == 2 {
+                    next.push(evaluator.relinearize(&evaluator.mul(&pair[0], &pair[1]), ek));
+                } else {
+                    next.push(pair[0].clone());
+                }
+            }
+            eqs = next;
+        }
+        eqs.pop().unwrap()
+    }
+
+    /// Returns `self + other` modulo `2^(limb_bits * limbs.len())`.
+    ///
+    /// Carries are propagated sequentially from the least significant limb. Carry out of `i^th` limb is
+    /// `1 - [s_i < 2^limb_bits]`, where `s_i` is sum of `i^th` limbs and incoming carry, and the limb is
+    /// reduced to `s_i - 2^limb_bits * carry`. Thus each limb costs a comparison against plaintext.
+    pub fn add(
+        &self,
+        evaluator: &Evaluator,
+        other: &WideInt,
+        lt: &LessThanCoefficients,
+        ek: &EvaluationKey,
+    ) -> WideInt {
+        self.assert_compatible(other);
+
+        let degree = evaluator.params().degree;
+        let base = 1u64 << self.limb_bits;
+        let base_vec = vec![base; degree];
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut carry: Option<Ciphertext> = None;
+        for (a, b) in self.limbs.iter().zip(other.limbs.iter()) {
+            let mut sum = evaluator.add(a, b);
+            if let Some(c) = carry.as_ref() {
+                evaluator.add_assign(&mut sum, c);
+            }
+
+            let s_lt_base = less_than_plain(evaluator, &sum, &base_vec, lt, ek);
+            let c = one_minus(evaluator, &s_lt_base);
+
+            // s_i - base * carry
+            evaluator.sub_assign(&mut sum, &mul_plain(evaluator, &c, &base_vec));
+
+            limbs.push(sum);
+            carry = Some(c);
+        }
+
+        WideInt {
+            limbs,
+            limb_bits: self.limb_bits,
+        }
+    }
+
+    fn assert_compatible(&self, other: &WideInt) {
+        assert!(self.limbs.len() == other.limbs.len());
+        assert!(self.limb_bits == other.limb_bits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bfv::BfvParameters;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn wide_int_less_than_and_equal_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 15], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let limb_bits = WideInt::max_limb_bits(evaluator.params().plaintext_modulus);
+        let limb_count = WideInt::limbs_for_bits(32, limb_bits);
+
+        let degree = evaluator.params().degree;
+        let mx = (0..degree)
+            .map(|_| rng.gen::<u32>() as u64)
+            .collect::<Vec<u64>>();
+        // half of the values share the most significant limb with `mx` and some are equal
+        let my = mx
+            .iter()
+            .enumerate()
+            .map(|(i, v)| match i % 4 {
+                0 => *v,
+                1 => *v ^ 1,
+                2 => *v ^ (1 << limb_bits),
+                _ => rng.gen::<u32>() as u64,
+            })
+            .collect::<Vec<u64>>();
+
+        let x = WideInt::encrypt(&evaluator, &sk, &mx, limb_count, &mut rng);
+        let y = WideInt::encrypt(&evaluator, &sk, &my, limb_count, &mut rng);
+        assert_eq!(
+            x.decrypt(&evaluator, &sk),
+            mx.iter().map(|v| *v as u128).collect::<Vec<u128>>()
+        );
+
+        let decrypt = |ct: &Ciphertext| -> Vec<u64> {
+            evaluator.plaintext_decode(&evaluator.decrypt(&sk, ct), Encoding::default())
+        };
+
+        let res_lt = decrypt(&x.less_than(&evaluator, &y, &lt, &ek));
+        let res_eq = decrypt(&x.equal(&evaluator, &y, &ek));
+        for i in 0..degree {
+            assert_eq!(res_lt[i], (mx[i] < my[i]) as u64);
+            assert_eq!(res_eq[i], (mx[i] == my[i]) as u64);
+        }
+    }
+
+    #[test]
+    fn wide_int_add_works() {
+        let mut rng = thread_rng();
+
+        let mut params = BfvParameters::new(&[60; 20], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[60; 3]);
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[], &[], &mut rng);
+        let evaluator = Evaluator::new(params);
+        let lt = LessThanCoefficients::new(evaluator.params());
+
+        let limb_bits = WideInt::max_limb_bits(evaluator.params().plaintext_modulus);
+        let limb_count = 2;
+        let modulus = 1u128 << (limb_bits * limb_count);
+
+        let degree = evaluator.params().degree;
+        let mx = (0..degree)
+            .map(|_| rng.gen_range(0..modulus) as u64)
+            .collect::<Vec<u64>>();
+        let my = (0..degree)
+            .map(|_| rng.gen_range(0..modulus) as u64)
+            .collect::<Vec<u64>>();
+
+        let x = WideInt::encrypt(&evaluator, &sk, &mx, limb_count, &mut rng);
+        let y = WideInt::encrypt(&evaluator, &sk, &my, limb_count, &mut rng);
+
+        let res = x.add(&evaluator, &y, &lt, &ek).decrypt(&evaluator, &sk);
+        let expected = mx
+            .iter()
+            .zip(my.iter())
+            .map(|(a, b)| (*a as u128 + *b as u128) % modulus)
+            .collect::<Vec<u128>>();
+        assert_eq!(res, expected);
+    }
+}
@@ -0,0 +1,512 @@
+use argon2::Argon2;
+use bfv::{
+    BfvParameters, EvaluationKey, EvaluationKeyProto, KeySwitchingMethod, SecretKey, SecretKeyProto,
+};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use prost::Message;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use traits::TryFromWithParameters;
+use zeroize::{Zeroize, Zeroizing};
+
+const INDEX_FILE: &str = "index.json";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Permissions of `<id>.sk`, which is only readable by the owner
+const SECRET_KEY_MODE: u32 = 0o600;
+/// Permissions of public files
+const PUBLIC_MODE: u32 = 0o644;
+
+#[derive(Debug)]
+pub enum KeyStoreError {
+    Io(io::Error),
+    Index(serde_json::Error),
+    /// No key with given id in the store, or key was pruned after its retention window
+    NotFound(String),
+    /// Wrong passphrase or tampered secret key file
+    Decryption,
+    /// Key files do not match the index or were stored under different parameters
+    Malformed,
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::Io(e) => write!(f, "key store io error: {e}"),
+            KeyStoreError::Index(e) => write!(f, "key store index error: {e}"),
+            KeyStoreError::NotFound(id) => write!(f, "key {id} not found"),
+            KeyStoreError::Decryption => write!(f, "failed to decrypt secret key"),
+            KeyStoreError::Malformed => write!(f, "malformed key file"),
+        }
+    }
+}
+
+impl std::error::Error for KeyStoreError {}
+
+impl From<io::Error> for KeyStoreError {
+    fn from(e: io::Error) -> Self {
+        KeyStoreError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for KeyStoreError {
+    fn from(e: serde_json::Error) -> Self {
+        KeyStoreError::Index(e)
+    }
+}
+
+/// Stored key pair. Timestamps are in seconds since unix epoch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyEntry {
+    pub id: String,
+    pub created_at: u64,
+    /// Set once key is replaced by `KeyStore::rotate`
+    pub retired_at: Option<u64>,
+    /// Hex encoded fingerprint of parameters the key pair was generated with
+    pub params_fingerprint: String,
+    /// Hex encoded SHA3-256 digest of `<id>.ek`
+    pub ek_digest: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Index {
+    active: Option<String>,
+    keys: Vec<KeyEntry>,
+}
+
+/// Persists key pairs (`SecretKey` and `EvaluationKey`) in a directory:
+///
+/// - `<id>.sk`: secret key proto encrypted with ChaCha20-Poly1305 under a key derived from the
+///   passphrase with Argon2id. File contains `salt || nonce || ciphertext`. Id, parameters
+///   fingerprint and digest of `<id>.ek` are authenticated as associated data.
+/// - `<id>.ek`: evaluation key proto in plain, since evaluation key is public.
+/// - `index.json`: ids of stored keys, their parameters fingerprints and `<id>.ek` digests, and
+///   id of the active one.
+///
+/// Loading checks parameters fingerprint and `<id>.ek` digest before decoding either key, thus
+/// tampered files or parameters other than the ones the keys were stored with are reported as
+/// `KeyStoreError::Malformed`. All files are written to a temporary file first, which is synced
+/// to disk before it is renamed, so that a crash never leaves a partially written file.
+///
+/// Rotation replaces the active key with a new one. Retired keys remain loadable for `retention`,
+/// so that ciphertexts encrypted under them can still be decrypted, and are deleted afterwards.
+pub struct KeyStore {
+    dir: PathBuf,
+    passphrase: Zeroizing<Vec<u8>>,
+    retention: Duration,
+    index: Index,
+}
+
+impl KeyStore {
+    /// Opens key store in `dir`, creating the directory if it does not exist
+    pub fn open(
+        dir: impl AsRef<Path>,
+        passphrase: &str,
+        retention: Duration,
+    ) -> Result<KeyStore, KeyStoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let index_path = dir.join(INDEX_FILE);
+        let index = if index_path.exists() {
+            serde_json::from_slice(&fs::read(&index_path)?)?
+        } else {
+            Index::default()
+        };
+
+        Ok(KeyStore {
+            dir,
+            passphrase: Zeroizing::new(passphrase.as_bytes().to_vec()),
+            retention,
+            index,
+        })
+    }
+
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.index.active.as_deref()
+    }
+
+    pub fn keys(&self) -> &[KeyEntry] {
+        &self.index.keys
+    }
+
+    /// Stores `sk` and `ek` under a fresh id and makes them the active key pair. Previously active
+    /// key is retired and keys retired for longer than retention window are deleted.
+    ///
+    /// Returns id of the new key pair.
+    pub fn rotate<R: CryptoRng + RngCore>(
+        &mut self,
+        params: &BfvParameters,
+        sk: &SecretKey,
+        ek: &EvaluationKey,
+        rng: &mut R,
+    ) -> Result<String, KeyStoreError> {
+        let mut id_bytes = [0u8; 16];
+        rng.fill_bytes(&mut id_bytes);
+        let id = to_hex(&id_bytes);
+
+        let ek_bytes = EvaluationKeyProto::try_from_with_parameters(ek, params).encode_to_vec();
+        let params_fingerprint = parameters_fingerprint(params);
+        let ek_digest = to_hex(&Sha3_256::digest(&ek_bytes));
+
+        let aad = associated_data(&id, &params_fingerprint, &ek_digest);
+        self.write_secret_key(&id, &aad, params, sk, rng)?;
+        self.write_atomic(&self.path(&id, "ek"), &ek_bytes, PUBLIC_MODE)?;
+
+        let now = unix_now();
+        if let Some(active) = self.index.active.take() {
+            if let Some(entry) = self.index.keys.iter_mut().find(|e| e.id == active) {
+                entry.retired_at = Some(now);
+            }
+        }
+        self.index.keys.push(KeyEntry {
+            id: id.clone(),
+            created_at: now,
+            retired_at: None,
+            params_fingerprint,
+            ek_digest,
+        });
+        self.index.active = Some(id.clone());
+
+        self.prune_at(now)?;
+        Ok(id)
+    }
+
+    /// Loads key pair with `id`
+    pub fn load(
+        &self,
+        id: &str,
+        params: &BfvParameters,
+    ) -> Result<(SecretKey, EvaluationKey), KeyStoreError> {
+        // only ids in the index are read, thus `id` cannot point outside of `dir`
+        let entry = self
+            .index
+            .keys
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| KeyStoreError::NotFound(id.to_string()))?;
+
+        // decoding panics on keys that don't match parameters, thus check both beforehand
+        if entry.params_fingerprint != parameters_fingerprint(params) {
+            return Err(KeyStoreError::Malformed);
+        }
+        let ek_bytes = fs::read(self.path(id, "ek"))?;
+        if entry.ek_digest != to_hex(&Sha3_256::digest(&ek_bytes)) {
+            return Err(KeyStoreError::Malformed);
+        }
+
+        let aad = associated_data(id, &entry.params_fingerprint, &entry.ek_digest);
+        let sk = self.read_secret_key(id, &aad, params)?;
+        let ek_proto = EvaluationKeyProto::decode(ek_bytes.as_slice())
+            .map_err(|_| KeyStoreError::Malformed)?;
+        let ek = EvaluationKey::try_from_with_parameters(&ek_proto, params);
+        Ok((sk, ek))
+    }
+
+    /// Loads active key pair
+    pub fn load_active(
+        &self,
+        params: &BfvParameters,
+    ) -> Result<(SecretKey, EvaluationKey), KeyStoreError> {
+        let id = self
+            .active_key_id()
+            .ok_or_else(|| KeyStoreError::NotFound("active".to_string()))?;
+        self.load(id, params)
+    }
+
+    /// Deletes keys retired for longer than retention window
+    pub fn prune(&mut self) -> Result<(), KeyStoreError> {
+        self.prune_at(unix_now())
+    }
+
+    fn prune_at(&mut self, now: u64) -> Result<(), KeyStoreError> {
+        let retention = self.retention.as_secs();
+        let (expired, kept): (Vec<KeyEntry>, Vec<KeyEntry>) =
+            self.index.keys.drain(..).partition(|e| {
+                e.retired_at
+                    .is_some_and(|retired_at| retired_at.saturating_add(retention) <= now)
+            });
+        self.index.keys = kept;
+        // update index before deleting files, so that index never refers to deleted keys
+        self.write_index()?;
+
+        for entry in expired {
+            for extension in ["sk", "ek"] {
+                match fs::remove_file(self.path(&entry.id, extension)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_index(&self) -> Result<(), KeyStoreError> {
+        self.write_atomic(
+            &self.dir.join(INDEX_FILE),
+            &serde_json::to_vec_pretty(&self.index)?,
+            PUBLIC_MODE,
+        )
+    }
+
+    /// Writes `bytes` to `path`, which is created with permissions `mode` on unix. Writes to a
+    /// temporary file, syncs it and renames it, then syncs the directory, so that crash leaves
+    /// either the old or the new file and the rename is durable.
+    fn write_atomic(&self, path: &Path, bytes: &[u8], mode: u32) -> Result<(), KeyStoreError> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        // temporary file left by a crash may have other permissions, thus always create a new one
+        match fs::remove_file(&tmp) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(mode);
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        let mut file = options.open(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp, path)?;
+        // directories can't be opened on windows, rename is durable there once it returns
+        #[cfg(unix)]
+        fs::File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+
+    fn write_secret_key<R: CryptoRng + RngCore>(
+        &self,
+        id: &str,
+        aad: &[u8],
+        params: &BfvParameters,
+        sk: &SecretKey,
+        rng: &mut R,
+    ) -> Result<(), KeyStoreError> {
+        let mut sk_proto = SecretKeyProto::try_from_with_parameters(sk, params);
+        let sk_bytes = Zeroizing::new(sk_proto.encode_to_vec());
+        sk_proto.coefficients.zeroize();
+
+        let mut salt = [0u8; SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+
+        let cipher = self.cipher(&salt);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &sk_bytes,
+                    aad,
+                },
+            )
+            .expect("Encryption of secret key failed");
+
+        let mut file = salt.to_vec();
+        file.extend_from_slice(&nonce);
+        file.extend_from_slice(&ciphertext);
+        self.write_atomic(&self.path(id, "sk"), &file, SECRET_KEY_MODE)
+    }
+
+    fn read_secret_key(
+        &self,
+        id: &str,
+        aad: &[u8],
+        params: &BfvParameters,
+    ) -> Result<SecretKey, KeyStoreError> {
+        let file = fs::read(self.path(id, "sk"))?;
+        if file.len() < SALT_LEN + NONCE_LEN {
+            return Err(KeyStoreError::Malformed);
+        }
+        let (salt, rest) = file.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let sk_bytes = Zeroizing::new(
+            self.cipher(salt)
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad,
+                    },
+                )
+                .map_err(|_| KeyStoreError::Decryption)?,
+        );
+
+        let mut sk_proto =
+            SecretKeyProto::decode(sk_bytes.as_slice()).map_err(|_| KeyStoreError::Malformed)?;
+        let sk = SecretKey::try_from_with_parameters(&sk_proto, params);
+        sk_proto.coefficients.zeroize();
+        Ok(sk)
+    }
+
+    /// Returns cipher keyed with Argon2id(passphrase, salt)
+    fn cipher(&self, salt: &[u8]) -> ChaCha20Poly1305 {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(&self.passphrase, salt, &mut key[..])
+            .expect("Key derivation failed");
+        ChaCha20Poly1305::new(Key::from_slice(&key[..]))
+    }
+
+    fn path(&self, id: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{id}.{extension}"))
+    }
+}
+
+/// Returns hex encoded SHA3-256 digest of parameters that determine encoding of keys
+fn parameters_fingerprint(params: &BfvParameters) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update((params.degree as u64).to_le_bytes());
+    hasher.update(params.plaintext_modulus.to_le_bytes());
+    params
+        .ciphertext_moduli
+        .iter()
+        .chain(params.special_moduli.iter().flatten())
+        .for_each(|q| hasher.update(q.to_le_bytes()));
+    hasher.update((params.ciphertext_moduli.len() as u64).to_le_bytes());
+    let (method, alpha) = match params.key_switching_method {
+        KeySwitchingMethod::BV => (0u8, 0),
+        KeySwitchingMethod::Hybrid => (1u8, params.alpha.unwrap_or(0) as u64),
+    };
+    hasher.update([method]);
+    hasher.update(alpha.to_le_bytes());
+    to_hex(&hasher.finalize())
+}
+
+/// Returns associated data of `<id>.sk`, which binds the secret key to its id, parameters and
+/// evaluation key
+fn associated_data(id: &str, params_fingerprint: &str, ek_digest: &str) -> Vec<u8> {
+    [id, params_fingerprint, ek_digest].join(":").into_bytes()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time before unix epoch")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn key_store_works() {
+        let mut rng = thread_rng();
+        let mut params = BfvParameters::new(&[50; 3], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[50; 3]);
+
+        let dir = std::env::temp_dir().join(format!("key-store-{}", rng.next_u64()));
+        let mut store = KeyStore::open(&dir, "passphrase", Duration::from_secs(3600)).unwrap();
+
+        let sk0 = SecretKey::random_with_params(&params, &mut rng);
+        let ek0 = EvaluationKey::new(&params, &sk0, &[0], &[0], &[1], &mut rng);
+        let id0 = store.rotate(&params, &sk0, &ek0, &mut rng).unwrap();
+
+        let sk1 = SecretKey::random_with_params(&params, &mut rng);
+        let ek1 = EvaluationKey::new(&params, &sk1, &[0], &[0], &[1], &mut rng);
+        let id1 = store.rotate(&params, &sk1, &ek1, &mut rng).unwrap();
+
+        // retired key is loadable within retention window
+        let store = KeyStore::open(&dir, "passphrase", Duration::from_secs(3600)).unwrap();
+        assert_eq!(store.active_key_id(), Some(id1.as_str()));
+        assert_eq!(store.load(&id0, &params).unwrap(), (sk0, ek0));
+        assert_eq!(store.load_active(&params).unwrap(), (sk1, ek1));
+
+        let wrong = KeyStore::open(&dir, "wrong", Duration::from_secs(3600)).unwrap();
+        assert!(matches!(
+            wrong.load(&id1, &params),
+            Err(KeyStoreError::Decryption)
+        ));
+
+        // retired key is deleted once retention window passes
+        let mut store = KeyStore::open(&dir, "passphrase", Duration::ZERO).unwrap();
+        store.prune().unwrap();
+        assert!(matches!(
+            store.load(&id0, &params),
+            Err(KeyStoreError::NotFound(_))
+        ));
+        assert!(!dir.join(format!("{id0}.sk")).exists());
+        assert!(store.load(&id1, &params).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_store_rejects_mismatching_keys() {
+        let mut rng = thread_rng();
+        let mut params = BfvParameters::new(&[50; 3], 65537, 1 << 4);
+        params.enable_hybrid_key_switching(&[50; 3]);
+
+        let dir = std::env::temp_dir().join(format!("key-store-{}", rng.next_u64()));
+        let mut store = KeyStore::open(&dir, "passphrase", Duration::from_secs(3600)).unwrap();
+
+        let sk = SecretKey::random_with_params(&params, &mut rng);
+        let ek = EvaluationKey::new(&params, &sk, &[0], &[0], &[1], &mut rng);
+        let id = store.rotate(&params, &sk, &ek, &mut rng).unwrap();
+        // no temporary files are left behind
+        let files = fs::read_dir(&dir)
+            .unwrap()
+            .map(|f| f.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        assert!(files.iter().all(|f| !f.ends_with(".tmp")));
+        // secret key is only readable by the owner
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(store.path(&id, "sk"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, SECRET_KEY_MODE);
+        }
+
+        // other parameters
+        let mut other_params = BfvParameters::new(&[50; 4], 65537, 1 << 4);
+        other_params.enable_hybrid_key_switching(&[50; 3]);
+        assert!(matches!(
+            store.load(&id, &other_params),
+            Err(KeyStoreError::Malformed)
+        ));
+
+        // tampered evaluation key
+        let ek_path = dir.join(format!("{id}.ek"));
+        let mut ek_bytes = fs::read(&ek_path).unwrap();
+        ek_bytes[0] ^= 1;
+        fs::write(&ek_path, &ek_bytes).unwrap();
+        assert!(matches!(
+            store.load(&id, &params),
+            Err(KeyStoreError::Malformed)
+        ));
+
+        // tampered evaluation key with matching index digest fails to authenticate secret key
+        store.index.keys[0].ek_digest = to_hex(&Sha3_256::digest(&ek_bytes));
+        assert!(matches!(
+            store.load(&id, &params),
+            Err(KeyStoreError::Decryption)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,20 @@
+mod key_store;
+
 use bfv::*;
+use key_store::KeyStore;
 use operators::*;
 use rand::thread_rng;
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::fs::File;
 use std::io::Read;
+use std::time::Duration;
+
+/// Directory of the key store
+const KEY_STORE_DIR: &str = "keys";
+
+/// Retired keys remain available for decryption for 30 days
+const KEY_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Orders {
@@ -35,21 +46,50 @@ fn main() {
     // P - 180 bits
     params.enable_hybrid_key_switching(&[60; 3]);
 
-    println!("Generating secret key...");
-    let sk = SecretKey::random_with_params(&params, &mut rng);
-    println!("Secret key generated.");
+    println!("Opening key store...");
+    let passphrase = env::var("KEY_STORE_PASSPHRASE").expect("KEY_STORE_PASSPHRASE not set");
+    let mut key_store = KeyStore::open(KEY_STORE_DIR, &passphrase, KEY_RETENTION)
+        .expect("Failed to open key store");
+    // keys retired for longer than retention window are deleted even if keys are not rotated
+    key_store.prune().expect("Failed to prune key store");
+    if env::args().any(|arg| arg == "--list-keys") {
+        for entry in key_store.keys() {
+            let status = match entry.retired_at {
+                Some(retired_at) => format!("retired at {}", retired_at),
+                None => "active".to_string(),
+            };
+            println!("{} created at {} ({})", entry.id, entry.created_at, status);
+        }
+        return;
+    }
+    let rotate_keys = env::args().any(|arg| arg == "--rotate-keys");
+    let active_key_id = key_store.active_key_id().map(str::to_owned);
+    let (sk, ek) = match active_key_id {
+        Some(id) if !rotate_keys => {
+            println!("Loading keys {}...", id);
+            key_store.load_active(&params).expect("Failed to load keys")
+        }
+        _ => {
+            println!("Generating secret key and evaluation key...");
+            let sk = SecretKey::random_with_params(&params, &mut rng);
+            let ek = EvaluationKey::new(&params, &sk, &[0], &[0], &[1], &mut rng);
+            let id = key_store
+                .rotate(&params, &sk, &ek, &mut rng)
+                .expect("Failed to store keys");
+            println!("Keys stored with id {}.", id);
+            (sk, ek)
+        }
+    };
+    println!("Keys ready.");
     println!("------------------------------------------------");
 
     println!("Creating evaluator...");
     let evaluator = Evaluator::new(params);
+    // coefficients of comparison polynomial are only computed once
+    let lt = LessThanCoefficients::new(evaluator.params());
     println!("Evaluator created.");
     println!("------------------------------------------------");
 
-    println!("Generating evaluation key...");
-    let ek = EvaluationKey::new(evaluator.params(), &sk, &[0], &[0], &[1], &mut rng);
-    println!("Evaluation key generated.");
-    println!("------------------------------------------------");
-
     println!("Opening and reading the order file...");
     let file_path = "order.json";
     let mut file = File::open(file_path).expect("File not found");
@@ -152,7 +192,7 @@ fn main() {
 
     println!("Comparing sum of buy orders and sell orders (encrypted)...");
     let is_buy_sum_less_encrypted =
-        univariate_less_than(&evaluator, &sum_buy_orders, &sum_sell_orders, &ek, &sk);
+        univariate_less_than(&evaluator, &sum_buy_orders, &sum_sell_orders, &lt, &ek);
     println!("Comparison result (encrypted): {:?}", is_buy_sum_less_encrypted);
     println!("------------------------------------------------");
 
@@ -192,7 +232,7 @@ fn main() {
 
             for (index, order) in encrypted_buy_orders.iter().enumerate() {
                 let is_less_encrypted =
-                    univariate_less_than(&evaluator, order, &remaining_sell_orders_sum, &ek, &sk);
+                    univariate_less_than(&evaluator, order, &remaining_sell_orders_sum, &lt, &ek);
                 let is_less_plain = evaluator.plaintext_decode(
                     &evaluator.decrypt(&sk, &is_less_encrypted),
                     Encoding::default(),
@@ -236,7 +276,7 @@ fn main() {
 
             for (index, order) in encrypted_sell_orders.iter().enumerate() {
                 let is_less_encrypted =
-                    univariate_less_than(&evaluator, order, &remaining_buy_orders_sum, &ek, &sk);
+                    univariate_less_than(&evaluator, order, &remaining_buy_orders_sum, &lt, &ek);
                 let is_less_plain = evaluator.plaintext_decode(
                     &evaluator.decrypt(&sk, &is_less_encrypted),
                     Encoding::default(),